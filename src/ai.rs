@@ -1,77 +1,116 @@
-use crate::curses::{Status, PLAYER};
+use crate::curses::Status;
 use crate::game::Game;
+use crate::map_gen::Rect;
 use crate::object;
+use crate::rng;
+use crate::shop;
+use crate::store::ObjectId;
 
 use rand::Rng;
-use std::cmp;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AI {
     Basic,
-    Confused { prev_ai: Box<AI>, turns: i32 },
+    Confused {
+        prev_ai: Box<AI>,
+        turns: i32,
+    },
+    Asleep {
+        prev_ai: Box<AI>,
+        turns: i32,
+    },
+    Shopkeeper {
+        shop: Rect,
+        post: (i32, i32),
+        aside: (i32, i32),
+    },
 }
-pub fn take_turn(monster_id: usize, game: &mut Game) {
-    // only move if close
-    let monster = &game.graphics.objects.borrow()[monster_id].clone();
-    let player = &game.graphics.objects.borrow()[PLAYER].clone();
-    match &monster.ai {
-        Some(AI::Basic) => {
-            if monster.distance_to(&game.graphics.objects.borrow()[PLAYER]) <= 6.0 {
-                if monster.distance_to(&game.graphics.objects.borrow()[PLAYER]) >= 2.0 {
-                    // move towards player if far away
-                    let (player_x, player_y) = player.pos();
-                    object::move_towards(
-                        monster_id,
-                        player_x,
-                        player_y,
-                        &game.map,
-                        &mut game.graphics.objects.borrow_mut(),
-                    );
-                } else if player.fighter.map_or(false, |f| f.hp > 0) {
-                    // close enough, attack! (if the player is still alive.)
-                    let mut objs = game.graphics.objects.borrow_mut();
-                    let (monster, mut player) = mut_two(monster_id, PLAYER, &mut objs);
-                    monster.attack(&mut player, &mut game.graphics.statuses, &game.inventory)
-                }
+pub fn take_turn(monster_id: ObjectId, game: &mut Game) {
+    let mut world = game.world.borrow_mut();
+    let ai = match world.ais.get(monster_id) {
+        Some(ai) => ai.clone(),
+        None => return,
+    };
+    let player_id = world.player_id();
+    let (player_x, player_y) = world.pos(player_id);
+    let distance = world.distance(monster_id, player_id);
+    match ai {
+        // only move if close
+        AI::Basic if distance <= 6.0 => {
+            if distance >= 2.0 {
+                // move towards player if far away
+                object::move_towards(&mut world, monster_id, player_x, player_y, &game.map);
+            } else if world.is_alive(player_id) {
+                // close enough, attack! (if the player is still alive.)
+                object::attack(&mut world, monster_id, player_id, &mut game.statuses);
             }
         }
-        Some(AI::Confused { prev_ai, turns }) => {
-            if turns >= &0 {
+        AI::Confused { prev_ai, turns } => {
+            if turns >= 0 {
                 // still confused ...
                 // move in a random direction, and decrease the number of turns confused
-                {
-                    object::move_by(
-                        monster_id,
-                        rand::thread_rng().gen_range(-1, 2),
-                        rand::thread_rng().gen_range(-1, 2),
-                        &game.map,
-                        &mut game.graphics.objects.borrow_mut(),
-                    );
-                }
-                game.graphics.objects.borrow_mut()[monster_id].ai = Some(AI::Confused {
-                    prev_ai: prev_ai.clone(),
-                    turns: turns - 1,
-                });
+                object::move_by(
+                    &mut world,
+                    monster_id,
+                    rng::get().gen_range(-1, 2),
+                    rng::get().gen_range(-1, 2),
+                    &game.map,
+                );
+                world.ais.insert(
+                    monster_id,
+                    AI::Confused {
+                        prev_ai,
+                        turns: turns - 1,
+                    },
+                );
             } else {
                 // restore the previous AI (this one will be deleted)
-                game.graphics.statuses.push(Status::new(
-                    format!("The {} is no longer confused!", monster.name),
+                game.statuses.push(Status::new(
+                    format!("The {} is no longer confused!", world.name(monster_id)),
                     1,
                 ));
-                game.graphics.objects.borrow_mut()[monster_id].ai = Some(*prev_ai.clone());
+                world.ais.insert(monster_id, *prev_ai);
             }
         }
-        _ => (),
-    }
-}
+        AI::Asleep { prev_ai, turns } => {
+            if turns >= 0 {
+                world.ais.insert(
+                    monster_id,
+                    AI::Asleep {
+                        prev_ai,
+                        turns: turns - 1,
+                    },
+                );
+            } else {
+                game.statuses.push(Status::new(
+                    format!("The {} wakes up!", world.name(monster_id)),
+                    1,
+                ));
+                world.ais.insert(monster_id, *prev_ai);
+            }
+        }
+        AI::Shopkeeper { shop, post, aside } => {
+            let hurt = world
+                .fighters
+                .get(monster_id)
+                .is_some_and(|f| f.hp < f.base_max_hp);
+            let unpaid = world
+                .inventory(player_id)
+                .iter()
+                .any(|item| world.for_sale.get(*item) == Some(&monster_id));
 
-pub fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
-    assert!(first_index != second_index);
-    let split_at_index = cmp::max(first_index, second_index);
-    let (first_slice, second_slice) = items.split_at_mut(split_at_index);
-    if first_index < second_index {
-        (&mut first_slice[first_index], &mut second_slice[0])
-    } else {
-        (&mut second_slice[0], &mut first_slice[second_index])
+            if hurt || (unpaid && !shop.contains(player_x, player_y)) {
+                drop(world);
+                shop::anger_shopkeeper(monster_id, game);
+            } else {
+                // block the door while the customer has unpaid items
+                let (x, y) = if unpaid { post } else { aside };
+                if world.pos(monster_id) != (x, y) {
+                    object::move_towards(&mut world, monster_id, x, y, &game.map);
+                }
+            }
+        }
+        _ => (),
     }
 }
@@ -0,0 +1,156 @@
+use crate::game::{self, Game};
+use crate::item::Slot;
+use crate::object;
+
+/// the kill counts get at most this many lines
+const KILL_LINES: usize = 2;
+
+/// Everything about the player on one screen: where their stats come from,
+/// what ails them, what they wear and what they have killed. Lines are kept
+/// to `width` columns.
+pub fn sheet(game: &Game, width: usize) -> Vec<String> {
+    let world = game.world.borrow();
+    let player_id = world.player_id();
+    let fighter = world.fighters[player_id];
+    let experience = world.experience[player_id];
+    let vitals = world.vitals[player_id];
+    let mut lines = vec![];
+
+    lines.push(format!(
+        "{}, HP {}/{}, level {}, XP {}/{}, {} gold",
+        world.name(player_id),
+        fighter.hp,
+        object::max_hp(&world, player_id),
+        experience.level,
+        experience.xp,
+        experience.level_up_xp,
+        world.gold.get(player_id).unwrap_or(&0)
+    ));
+    lines.push(format!(
+        "Dungeon level {}, {} turns played",
+        game.dungeon_level, game.turns
+    ));
+    lines.push(String::new());
+
+    lines.push(format!(
+        "{:<10}{:>6}{:>11}{:>7}",
+        "", "Base", "Equipment", "Total"
+    ));
+    let stats = [
+        (
+            "Max HP",
+            fighter.base_max_hp,
+            object::max_hp(&world, player_id),
+        ),
+        (
+            "Power",
+            fighter.base_power,
+            object::power(&world, player_id),
+        ),
+        (
+            "Defence",
+            fighter.base_defence,
+            object::defence(&world, player_id),
+        ),
+    ];
+    for (name, base, total) in stats {
+        lines.push(format!(
+            "{:<10}{:>6}{:>+11}{:>7}",
+            name,
+            base,
+            total - base,
+            total
+        ));
+    }
+    lines.push(String::new());
+
+    let mut effects = vec![];
+    if vitals.poisoned > 0 {
+        effects.push(format!("poisoned for {} more turns", vitals.poisoned));
+    }
+    if game::can_regenerate(&world) {
+        effects.push(format!(
+            "healing 1 HP every {} turns",
+            game::regen_interval(&world)
+        ));
+    } else {
+        effects.push("not healing".to_string());
+    }
+    lines.push(format!("Status: {}", effects.join(", ")));
+    lines.push(format!(
+        "Hunger: {} (nutrition {})",
+        vitals.hunger(),
+        vitals.nutrition
+    ));
+    lines.push(String::new());
+
+    lines.push("Equipment:".to_string());
+    for slot in [Slot::LeftHand, Slot::RightHand, Slot::Head] {
+        let worn = object::get_equipped_in_slot(&world, player_id, slot);
+        let description = match worn.and_then(|item| Some((item, world.equipment.get(item)?))) {
+            Some((item, equipment)) => {
+                let bonuses = [
+                    (equipment.power_bonus, "power"),
+                    (equipment.defense_bonus, "defence"),
+                    (equipment.max_hp_bonus, "max HP"),
+                ]
+                .iter()
+                .filter(|(bonus, _)| *bonus != 0)
+                .map(|(bonus, stat)| format!("{:+} {}", bonus, stat))
+                .collect::<Vec<_>>();
+                if bonuses.is_empty() {
+                    world.name(item).to_string()
+                } else {
+                    format!("{} ({})", world.name(item), bonuses.join(", "))
+                }
+            }
+            None => "(nothing)".to_string(),
+        };
+        lines.push(format!("  {:<11} {}", slot.to_string(), description));
+    }
+    lines.push(String::new());
+
+    let kills = game.kills_by_count();
+    let total: u32 = kills.iter().map(|(_, count)| count).sum();
+    let kills = kills
+        .iter()
+        .map(|(monster, count)| format!("{} {}", count, monster))
+        .collect::<Vec<_>>();
+    if kills.is_empty() {
+        lines.push("Kills: none".to_string());
+    } else {
+        lines.extend(wrap(
+            &format!("Kills ({}): {}", total, kills.join(", ")),
+            width,
+            KILL_LINES,
+        ));
+    }
+
+    lines
+        .into_iter()
+        .map(|line| line.chars().take(width).collect())
+        .collect()
+}
+
+/// break text into lines at spaces, ending with `...` if it takes more than
+/// `max_lines`
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for word in text.split(' ') {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            let keep = width.saturating_sub(4);
+            *last = format!("{} ...", last.chars().take(keep).collect::<String>());
+        }
+    }
+    lines
+}
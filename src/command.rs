@@ -0,0 +1,48 @@
+/// Something the player wants to do, whether it came from the keyboard, a bot
+/// or a replay. `Game::execute` carries it out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// one step, attacking whatever is in the way
+    Move(i32, i32),
+    /// keep moving in a direction until something interesting comes up,
+    /// following corridors around their bends
+    Run(i32, i32),
+    /// walk to a place on the map the player has seen
+    Travel(i32, i32),
+    /// pick up the item underfoot, or gold if there is no item
+    Pickup,
+    /// do nothing for a turn
+    Wait,
+    /// wait until healed, or until something interesting happens
+    Rest,
+    /// walk to what hasn't been seen yet, until something interesting happens
+    Explore,
+    /// Use the item at a position of the inventory. Wands are zapped in
+    /// `direction`, a scroll of recharging recharges the wand at `target`.
+    Apply {
+        slot: usize,
+        direction: Option<(i32, i32)>,
+        target: Option<usize>,
+    },
+    Drop(usize),
+    /// buy the unpaid item at a position of the inventory from its shopkeeper
+    Buy(usize),
+    /// sell the item at a position of the inventory to the shop the player is in
+    Sell(usize),
+    /// take the stairs down, when standing on them
+    Descend,
+    /// spend the experience for the next level on a stat
+    LevelUp(Stat),
+    /// save the run to continue it later, and leave
+    Quit,
+    /// give the run up for good, leaving only the morgue file
+    Abandon,
+}
+
+/// what a level up can raise
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stat {
+    Constitution,
+    Strength,
+    Agility,
+}
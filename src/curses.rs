@@ -1,198 +1,935 @@
-use std::cell::RefCell;
+use crate::highscore;
+use crate::object;
+use crate::object::Hunger;
+use crate::theme::{Color, Theme};
+use crate::tile::{self, Map};
+use crate::world::World;
+use pancurses::{chtype, Input, Window, A_BOLD, A_REVERSE};
 
-use crate::object::Object;
-use crate::tile::{Map, MAP_HEIGHT, MAP_WIDTH};
-use pancurses::{Window, A_BOLD};
+/// the smallest terminal the game can be played in
+pub const MIN_WIDTH: i32 = 80;
+pub const MIN_HEIGHT: i32 = 24;
 
-pub const SCR_WIDTH: i32 = 130;
-pub const INV_X: i32 = 101;
+/// columns for the inventory, and the narrowest terminal that still gets them
+const INVENTORY_WIDTH: i32 = 29;
+const INVENTORY_MIN_SCREEN_WIDTH: i32 = 110;
 
-pub const WINDOW_WIDTH: i32 = 99;
-pub const WINDOW_HEIGHT: i32 = 40;
+/// columns for the player's stats under the map
+const STATS_WIDTH: i32 = 25;
+/// rows under the map for the message log and the stats, borders included
+const BOTTOM_PANEL_HEIGHT: i32 = 12;
 
-pub const STATUS_Y: i32 = 32;
-pub const STATUS_HEIGHT: i32 = WINDOW_HEIGHT - STATUS_Y;
+const MAX_LOG: usize = 500;
 
-pub const PLAYER_STATS_X: i32 = WINDOW_WIDTH / 4 + WINDOW_WIDTH / 2 + 1;
+/// the keys options get when they aren't given one, in order
+const MENU_LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 
-pub const PLAYER: usize = 0;
+/// Where everything goes on the screen. It is worked out from the size of the
+/// terminal whenever something is drawn, so after a resize a redraw is all it
+/// takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub width: i32,
+    pub height: i32,
+    /// the map view, inside the borders
+    pub map_x: i32,
+    pub map_y: i32,
+    pub map_width: i32,
+    pub map_height: i32,
+    /// the border right of the map; the right edge when there is no inventory
+    pub divider_x: i32,
+    /// the inventory panel right of the map, left out on narrow terminals
+    pub inventory_x: Option<i32>,
+    /// the first row of the message log, with the stats next to it
+    pub status_y: i32,
+    pub stats_x: i32,
+}
+
+impl Layout {
+    pub fn new(width: i32, height: i32) -> Self {
+        let right = width - 1;
+        let (divider_x, inventory_x) = if width >= INVENTORY_MIN_SCREEN_WIDTH {
+            (right - INVENTORY_WIDTH - 2, Some(right - INVENTORY_WIDTH))
+        } else {
+            (right, None)
+        };
+        let status_y = height - BOTTOM_PANEL_HEIGHT + 3;
+        Self {
+            width,
+            height,
+            map_x: 1,
+            map_y: 1,
+            map_width: divider_x - 1,
+            map_height: status_y - 4,
+            divider_x,
+            inventory_x,
+            status_y,
+            stats_x: divider_x - STATS_WIDTH,
+        }
+    }
+
+    pub fn is_too_small(&self) -> bool {
+        self.width < MIN_WIDTH || self.height < MIN_HEIGHT
+    }
+
+    /// the bottom border
+    pub fn bottom(&self) -> i32 {
+        self.height - 1
+    }
+
+    /// how many messages fit in the log under the map
+    pub fn message_lines(&self) -> usize {
+        (self.bottom() - self.status_y + 1).max(0) as usize
+    }
+}
+
+/// The part of the map that is on screen: the map position shown at the top
+/// left of the view, and where the view is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Camera {
+    pub x: i32,
+    pub y: i32,
+    screen_x: i32,
+    screen_y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Camera {
+    /// keep a position in the middle of the view, except near the edges of the
+    /// map, which stay at the edges of the view; a map smaller than the view is
+    /// centered in it
+    pub fn follow(x: i32, y: i32, map: &Map, layout: &Layout) -> Self {
+        fn axis(target: i32, map_len: i32, view_len: i32) -> i32 {
+            if map_len <= view_len {
+                -(view_len - map_len) / 2
+            } else {
+                (target - view_len / 2).clamp(0, map_len - view_len)
+            }
+        }
+        Self {
+            x: axis(x, tile::width(map), layout.map_width),
+            y: axis(y, tile::height(map), layout.map_height),
+            screen_x: layout.map_x,
+            screen_y: layout.map_y,
+            width: layout.map_width,
+            height: layout.map_height,
+        }
+    }
 
-/// Handles drawing. Expects player to be the first in the vector.
+    /// where a map position is on the screen, None if it is out of view
+    pub fn to_screen(self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let (view_x, view_y) = (x - self.x, y - self.y);
+        if (0..self.width).contains(&view_x) && (0..self.height).contains(&view_y) {
+            Some((self.screen_x + view_x, self.screen_y + view_y))
+        } else {
+            None
+        }
+    }
+}
+
+/// Which line-drawing character a wall is, named like the curses ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WallShape {
+    Plus,
+    VLine,
+    HLine,
+    ULCorner,
+    URCorner,
+    LLCorner,
+    LRCorner,
+    LTee,
+    RTee,
+    TTee,
+    BTee,
+}
+
+impl WallShape {
+    /// the shape that joins a wall up with the known walls next to it
+    fn at(map: &Map, x: i32, y: i32) -> Self {
+        let wall = |dx: i32, dy: i32| {
+            let (x, y) = (x + dx, y + dy);
+            tile::in_bounds(x, y, map)
+                && map[x as usize][y as usize].visible
+                && map[x as usize][y as usize].block_sight
+        };
+        match (wall(0, -1), wall(1, 0), wall(0, 1), wall(-1, 0)) {
+            // north, east, south, west
+            (false, false, false, false) => WallShape::Plus,
+            (_, false, _, false) => WallShape::VLine,
+            (false, _, false, _) => WallShape::HLine,
+            (false, true, true, false) => WallShape::ULCorner,
+            (false, false, true, true) => WallShape::URCorner,
+            (true, true, false, false) => WallShape::LLCorner,
+            (true, false, false, true) => WallShape::LRCorner,
+            (true, true, true, false) => WallShape::LTee,
+            (true, false, true, true) => WallShape::RTee,
+            (false, true, true, true) => WallShape::TTee,
+            (true, true, false, true) => WallShape::BTee,
+            (true, true, true, true) => WallShape::Plus,
+        }
+    }
+}
+
+/// Handles drawing and asking the player things. Expects one of the objects
+/// of the world it draws to be the player.
 pub struct Graphics {
-    pub objects: RefCell<Vec<Object>>,
+    /// where the map was last drawn from
+    pub camera: Camera,
+    /// a map position to keep in view instead of the player, e.g. a cursor
+    pub look_at: Option<(i32, i32)>,
+    /// walls are drawn with line-drawing characters rather than `#`
+    pub line_walls: bool,
+    pub theme: Theme,
     pub window: Window,
-    pub statuses: Vec<Status>,
+}
+
+/// where a menu is drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placement {
+    /// in a box over the middle of the map
+    Box,
+    /// on one line in place of the first message, leaving the map in view
+    Prompt,
+    /// on the bottom line, under a screen of its own
+    Footer,
+}
+
+/// A list of options to pick from by the keys in front of them, shown by
+/// `Graphics::choose` in a box over the screen unless it is placed elsewhere.
+#[derive(Clone, Debug)]
+pub struct Menu {
+    title: String,
+    /// the keys that pick each option; the first character among them is listed
+    options: Vec<(Vec<Input>, String)>,
+    placement: Placement,
+}
+
+impl Menu {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            options: vec![],
+            placement: Placement::Box,
+        }
+    }
+
+    /// an option with the letter for its position, a to z and then A to Z;
+    /// options past those can only be seen
+    pub fn option(mut self, label: &str) -> Self {
+        let key = MENU_LETTERS.chars().nth(self.options.len());
+        let keys = key.map(Input::Character).into_iter().collect();
+        self.options.push((keys, label.to_string()));
+        self
+    }
+
+    /// an option picked with a key of its own
+    pub fn keyed(mut self, key: char, label: &str) -> Self {
+        self.options
+            .push((vec![Input::Character(key)], label.to_string()));
+        self
+    }
+
+    /// an option picked with any of several keys, like the ones bound to an
+    /// action; options without a label only list their key
+    pub fn keyed_by(mut self, keys: &[Input], label: &str) -> Self {
+        self.options.push((keys.to_vec(), label.to_string()));
+        self
+    }
+
+    /// list the options in place of the first message instead, for questions
+    /// about what is on the map
+    pub fn prompt(mut self) -> Self {
+        self.placement = Placement::Prompt;
+        self
+    }
+
+    /// list the options on the bottom line instead, for screens that fill the
+    /// terminal and are drawn by `Graphics::choose_on`
+    pub fn footer(mut self) -> Self {
+        self.placement = Placement::Footer;
+        self
+    }
+
+    /// the character an option is listed with
+    fn listed_key(keys: &[Input]) -> Option<char> {
+        keys.iter().find_map(|key| match key {
+            Input::Character(c) => Some(*c),
+            _ => None,
+        })
+    }
+
+    /// the title and the options on one line
+    fn line(&self) -> String {
+        let options = self.options.iter().filter_map(|(keys, label)| {
+            let key = Self::listed_key(keys)?;
+            Some(if label.is_empty() {
+                key.to_string()
+            } else {
+                format!("{} - {}", key, label)
+            })
+        });
+        let options = options.collect::<Vec<_>>().join(", ");
+        if self.title.is_empty() {
+            options
+        } else {
+            format!("{} {}", self.title, options)
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Status {
     msg: String,
     rounds: u32,
+    /// how much the message matters
+    color: Color,
 }
 
 impl Status {
     pub fn new(msg: String, rounds: u32) -> Self {
-        Self { msg, rounds }
+        Self {
+            msg,
+            rounds,
+            color: Color::Message,
+        }
+    }
+
+    /// the same message, in a color that tells good news from bad
+    pub fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+}
+
+/// a round has passed on screen: messages whose time is up go to the log,
+/// which keeps only the latest ones
+pub fn age_statuses(statuses: &mut Vec<Status>, log: &mut Vec<String>) {
+    for status in statuses.iter_mut() {
+        status.rounds -= 1;
+        if status.rounds == 0 {
+            log.push(status.msg.clone());
+        }
+    }
+    statuses.retain(|status| status.rounds != 0);
+
+    if log.len() > MAX_LOG {
+        let excess = log.len() - MAX_LOG;
+        log.drain(..excess);
     }
 }
 
 impl Graphics {
-    pub fn draw(&mut self, map: &Map) {
+    /// the layout for the terminal as big as it is now
+    pub fn layout(&self) -> Layout {
+        let (height, width) = self.window.get_max_yx();
+        Layout::new(width, height)
+    }
+
+    pub fn draw(&mut self, map: &Map, world: &World, statuses: &[Status]) {
         self.window.clear();
 
-        self.draw_borders();
+        let layout = self.layout();
+        if layout.is_too_small() {
+            self.draw_too_small(&layout);
+            return;
+        }
 
-        for y in 0..MAP_HEIGHT {
-            for x in 0..MAP_WIDTH {
-                if map[x as usize][y as usize].visible {
-                    let wall = map[x as usize][y as usize].block_sight;
-                    if wall {
-                        self.window.mvaddch(y, x, '+');
-                    } else {
-                        self.window.mvaddch(y, x, '.');
-                    }
-                }
-            }
+        self.draw_borders(&layout);
+
+        let player_id = world.player_id();
+        let player = world.positions.get(player_id).map(|p| (p.x, p.y));
+        if let Some((x, y)) = self.look_at.or(player) {
+            self.camera = Camera::follow(x, y, map, &layout);
         }
+        let camera = self.camera;
 
-        for obj in &*self.objects.borrow() {
-            if map[obj.x as usize][obj.y as usize].currently_visible {
-                obj.draw(&self.window);
+        for view_y in 0..layout.map_height {
+            for view_x in 0..layout.map_width {
+                let (x, y) = (camera.x + view_x, camera.y + view_y);
+                if !tile::in_bounds(x, y, map) || !map[x as usize][y as usize].visible {
+                    continue;
+                }
+                let tile = &map[x as usize][y as usize];
+                let (ch, color) = if tile.block_sight {
+                    (self.wall_glyph(map, x, y), Color::Wall)
+                } else {
+                    ('.' as chtype, Color::Floor)
+                };
+                // objects seen here last time are drawn over the tile
+                let ch = match tile.remembered {
+                    Some(remembered) if !tile.currently_visible => remembered.ch as chtype,
+                    _ => ch,
+                };
+                let color = if tile.currently_visible {
+                    color
+                } else {
+                    Color::Remembered
+                };
+                self.draw_colored(color, || {
+                    self.window
+                        .mvaddch(layout.map_y + view_y, layout.map_x + view_x, ch);
+                });
             }
         }
 
-        // draw alive objects with priority
-        let _ = self
-            .objects
-            .borrow()
-            .iter()
-            .filter(|obj| obj.alive && map[obj.x as usize][obj.y as usize].currently_visible)
-            .map(|obj| obj.draw(&self.window));
+        let visible = world.renderables.iter().filter_map(|(id, renderable)| {
+            let position = world.positions.get(id)?;
+            if !map[position.x as usize][position.y as usize].currently_visible {
+                return None;
+            }
+            let (x, y) = camera.to_screen(position.x, position.y)?;
+            Some((id, x, y, renderable))
+        });
 
-        // draw player with priority
-        self.objects.borrow()[PLAYER].draw(&self.window);
+        // draw alive objects with priority, and the player over everything
+        let (alive, others): (Vec<_>, Vec<_>) =
+            visible.partition(|(id, _, _, _)| world.is_alive(*id));
+        for (_, x, y, renderable) in others.into_iter().chain(alive) {
+            renderable.draw(x, y, &self.window, &self.theme);
+        }
+        let player_on_screen = world
+            .positions
+            .get(player_id)
+            .and_then(|position| camera.to_screen(position.x, position.y));
+        if let (Some((x, y)), Some(renderable)) =
+            (player_on_screen, world.renderables.get(player_id))
+        {
+            renderable.draw(x, y, &self.window, &self.theme);
+        }
 
-        self.window.mvaddstr(STATUS_Y - 2, 1, "Message log:");
+        self.window.mvaddstr(layout.status_y - 2, 1, "Message log:");
 
-        for y in (STATUS_Y - 2)..WINDOW_HEIGHT {
-            self.window.mvaddch(y, PLAYER_STATS_X - 1, '|');
+        for y in (layout.status_y - 2)..layout.bottom() {
+            self.window.mvaddch(y, layout.stats_x - 1, '|');
         }
 
-        for (i, status) in self.statuses.iter_mut().enumerate() {
+        let message_width = (layout.stats_x - 2) as usize;
+        // whatever doesn't fit is still in the log
+        for (i, status) in statuses.iter().take(layout.message_lines()).enumerate() {
+            let line = status.msg.chars().take(message_width).collect::<String>();
+            let attributes = self.theme.attributes(status.color);
+            self.window.attron(attributes);
             self.window
-                .mvaddstr(STATUS_Y - 1 + i as i32, 1, status.msg.clone());
-            status.rounds -= 1;
+                .mvaddstr(layout.status_y - 1 + i as i32, 1, line);
+            self.window.attroff(attributes);
         }
 
-        self.statuses = self
-            .statuses
-            .clone()
-            .into_iter()
-            .filter(|status| status.rounds != 0)
-            .collect();
+        self.window.refresh();
+    }
 
+    /// highlight a map position over the last drawn screen, with a prompt in
+    /// place of the first message
+    pub fn draw_cursor(&self, x: i32, y: i32, prompt: &str) {
+        let layout = self.layout();
+        if layout.is_too_small() {
+            return;
+        }
+        let width = (layout.stats_x - 2) as usize;
+        let prompt = format!("{:<width$}", prompt, width = width)
+            .chars()
+            .take(width)
+            .collect::<String>();
+        self.window.mvaddstr(layout.status_y - 1, 1, prompt);
+        if let Some((screen_x, screen_y)) = self.camera.to_screen(x, y) {
+            self.window.mvchgat(screen_y, screen_x, 1, A_REVERSE, 0);
+        }
         self.window.refresh();
     }
 
-    pub fn add_status(&mut self, msg: String, rounds: u32) {
-        self.statuses.push(Status::new(msg, rounds));
+    /// draw with another theme from now on
+    pub fn set_theme(&mut self, mut theme: Theme) {
+        theme.apply();
+        self.theme = theme;
     }
 
-    pub fn draw_player_stats(&self, player: &mut Object, level: u32, inventory: &Vec<Object>) {
-        if player.alive && player.fighter.unwrap().hp > 0 {
-            let hp = player.fighter.unwrap().hp;
+    pub fn draw_player_stats(&self, world: &World, level: u32) {
+        let layout = self.layout();
+        if layout.is_too_small() {
+            return;
+        }
+        let (x, y) = (layout.stats_x, layout.status_y);
+        let player_id = world.player_id();
+        let fighter = world.fighters[player_id];
+        let experience = world.experience[player_id];
+        let vitals = world.vitals[player_id];
+        let hp = fighter.hp;
+        let line = |y: i32, color: Color, text: String| {
+            self.draw_colored(color, || {
+                self.window.mvaddstr(y, x, text);
+            });
+        };
+
+        line(
+            y - 2,
+            if hp < 10 { Color::Danger } else { Color::Good },
+            format!("HP: {}/{}", fighter.hp, object::max_hp(world, player_id)),
+        );
+        line(y - 1, Color::Info, format!("Level: {}", experience.level));
+        line(
+            y,
+            Color::Message,
+            format!("XP: {}/{}", experience.xp, experience.level_up_xp),
+        );
+        line(
+            y + 1,
+            Color::Armour,
+            format!("Defence: {}", object::defence(world, player_id)),
+        );
+        line(
+            y + 2,
+            Color::Weapon,
+            format!("Power: {}", object::power(world, player_id)),
+        );
+        line(y + 3, Color::Stairs, format!("Dungeon Level: {}", level));
+        line(
+            y + 4,
+            Color::Gold,
+            format!("Gold: {}", world.gold.get(player_id).unwrap_or(&0)),
+        );
 
-            if hp < 10 {
-                self.window.color_set(pancurses::COLOR_RED);
+        let hunger = vitals.hunger();
+        if hunger != Hunger::NotHungry {
+            let color = if hunger == Hunger::Hungry {
+                Color::Warning
             } else {
-                self.window.color_set(pancurses::COLOR_GREEN);
+                Color::Danger
+            };
+            line(y + 5, color, format!("{}", hunger));
+        }
+
+        if vitals.poisoned > 0 {
+            line(y + 6, Color::Warning, "Poisoned".to_string());
+        }
+    }
+
+    /// draw something in a color of the theme
+    pub fn draw_colored(&self, color: Color, draw: impl FnOnce()) {
+        let attributes = self.theme.attributes(color);
+        self.window.attron(attributes);
+        draw();
+        self.window.attroff(attributes);
+    }
+
+    /// end of game summary, with the high score table
+    pub fn draw_death_screen(
+        &self,
+        summary: &[String],
+        entries: &[highscore::Entry],
+        rank: Option<usize>,
+    ) {
+        self.window.clear();
+
+        self.draw_colored(Color::Danger, || {
+            self.window.attron(A_BOLD);
+            self.window.mvaddstr(1, 2, "You're dead!");
+            self.window.attroff(A_BOLD);
+        });
+
+        for (i, line) in summary.iter().enumerate() {
+            self.window.mvaddstr(3 + i as i32, 2, line);
+        }
+
+        self.draw_high_scores(summary.len() as i32 + 4, entries, rank);
+        self.window.refresh();
+    }
+
+    /// A full screen list of lines with a title, e.g. the inventory, shown until
+    /// a key is pressed. Lists longer than the screen scroll a page at a time
+    /// with the same keys as menus.
+    pub fn show_text_screen(&self, title: &str, lines: &[String]) {
+        self.page_through(title, lines, 0);
+    }
+
+    /// the message log, starting with the latest messages
+    pub fn show_log(&self, log: &[String]) {
+        self.page_through("Message log", log, log.len());
+    }
+
+    fn page_through(&self, title: &str, lines: &[String], mut offset: usize) {
+        loop {
+            let shown = self.draw_text_screen(title, lines, &mut offset);
+            let scrolls = shown < lines.len();
+            match self.window.getch() {
+                Some(Input::KeyNPage) | Some(Input::Character('>')) if scrolls => offset += shown,
+                Some(Input::KeyPPage) | Some(Input::Character('<')) if scrolls => {
+                    offset = offset.saturating_sub(shown)
+                }
+                Some(Input::KeyResize) => {
+                    pancurses::resize_term(0, 0);
+                }
+                _ => break,
             }
+        }
+    }
 
-            self.window.mvaddstr(
-                STATUS_Y - 2,
-                PLAYER_STATS_X,
-                format!(
-                    "HP: {}/{}",
-                    player.fighter.unwrap().hp,
-                    player.max_hp(inventory)
-                ),
-            );
+    /// draw the page of a text screen starting at the line at `offset`, moved
+    /// back if that would leave the page short; returns how many lines fit
+    fn draw_text_screen(&self, title: &str, lines: &[String], offset: &mut usize) -> usize {
+        self.window.clear();
+        let layout = self.layout();
 
-            self.window.color_set(pancurses::COLOR_BLUE);
-            self.window.mvaddstr(
-                STATUS_Y - 1,
-                PLAYER_STATS_X,
-                format!("Level: {}", player.level),
-            );
+        self.window.attron(A_BOLD);
+        self.window.mvaddstr(1, 2, title);
+        self.window.attroff(A_BOLD);
 
-            self.window.color_set(pancurses::COLOR_WHITE);
-            self.window.mvaddstr(
-                STATUS_Y,
-                PLAYER_STATS_X,
-                format!("XP: {}/{}", player.fighter.unwrap().xp, player.level_up_xp),
-            );
+        // between the title and the hint at the bottom
+        let rows = (layout.height - 5).max(1) as usize;
+        let shown = lines.len().min(rows);
+        *offset = (*offset).min(lines.len() - shown);
+        let width = (layout.width - 4).max(0) as usize;
+        for (i, line) in lines.iter().skip(*offset).take(shown).enumerate() {
+            let line = line.chars().take(width).collect::<String>();
+            self.window.mvaddstr(3 + i as i32, 2, line);
+        }
 
-            self.window.color_set(pancurses::COLOR_YELLOW);
-            self.window.mvaddstr(
-                STATUS_Y + 1,
-                PLAYER_STATS_X,
-                format!("Defence: {}", player.defence(inventory)),
-            );
+        let hint = if shown < lines.len() {
+            "< > - scroll, any other key - go back"
+        } else {
+            "Press any key to go back."
+        };
+        self.window.mvaddstr(layout.bottom() - 1, 2, hint);
+        self.window.refresh();
+        shown
+    }
 
-            self.window.color_set(pancurses::COLOR_CYAN);
-            self.window.mvaddstr(
-                STATUS_Y + 2,
-                PLAYER_STATS_X,
-                format!("Power: {}", player.power(inventory)),
-            );
+    /// What has been seen of the level shrunk down to fit the screen, with the
+    /// player, the stairs and the objects seen lying around on it. The levels
+    /// visited are listed under it, the oldest ones scrolling off.
+    pub fn draw_overview(&self, map: &Map, player: (i32, i32), levels: &[String]) {
+        self.window.clear();
+        let layout = self.layout();
+        if layout.is_too_small() {
+            self.draw_too_small(&layout);
+            return;
+        }
 
-            self.window.color_set(pancurses::COLOR_RED);
+        self.window.attron(A_BOLD);
+        self.window.mvaddstr(1, 2, "Level overview");
+        self.window.attroff(A_BOLD);
+
+        // the list gets up to a third of the screen, the map the rest
+        let list_rows = (levels.len() as i32).clamp(1, (layout.height - 8) / 3);
+        let (view_width, view_height) = (layout.width - 4, layout.height - 7 - list_rows);
+        let (width, height) = (tile::width(map), tile::height(map));
+        let scale_x = (width + view_width - 1) / view_width;
+        let scale_y = (height + view_height - 1) / view_height;
+        let rows = (height + scale_y - 1) / scale_y;
+
+        for row in 0..rows {
+            for column in 0..(width + scale_x - 1) / scale_x {
+                let block = (column * scale_x..((column + 1) * scale_x).min(width))
+                    .flat_map(|x| {
+                        (row * scale_y..((row + 1) * scale_y).min(height)).map(move |y| (x, y))
+                    })
+                    .filter(|&(x, y)| map[x as usize][y as usize].visible)
+                    .collect::<Vec<_>>();
+                // the most interesting thing in the block stands for all of it
+                let remembered = block
+                    .iter()
+                    .filter_map(|&(x, y)| map[x as usize][y as usize].remembered)
+                    .max_by_key(|remembered| remembered.ch == '>');
+                let wall = block
+                    .iter()
+                    .find(|&&(x, y)| map[x as usize][y as usize].block_sight);
+                let (ch, color) = if block.contains(&player) {
+                    ('@' as chtype, Color::Player)
+                } else if let Some(remembered) = remembered {
+                    (remembered.ch as chtype, remembered.color)
+                } else if block
+                    .iter()
+                    .any(|&(x, y)| !map[x as usize][y as usize].block_sight)
+                {
+                    ('.' as chtype, Color::Floor)
+                } else if let Some(&(x, y)) = wall {
+                    // joined up walls only make sense at full size
+                    let ch = if scale_x == 1 && scale_y == 1 {
+                        self.wall_glyph(map, x, y)
+                    } else {
+                        '#' as chtype
+                    };
+                    (ch, Color::Wall)
+                } else {
+                    continue;
+                };
+                self.draw_colored(color, || {
+                    self.window.mvaddch(3 + row, 2 + column, ch);
+                });
+            }
+        }
+
+        let list_y = 3 + rows + 1;
+        self.window.attron(A_BOLD);
+        self.window.mvaddstr(list_y, 2, "Levels visited:");
+        self.window.attroff(A_BOLD);
+        let shown = &levels[levels.len().saturating_sub(list_rows as usize)..];
+        for (i, line) in shown.iter().enumerate() {
+            let line = line.chars().take(view_width as usize).collect::<String>();
+            self.window.mvaddstr(list_y + 1 + i as i32, 2, line);
+        }
+        self.window.refresh();
+    }
+
+    /// the option picked from a menu, None if it was cancelled with Escape
+    pub fn choose(&self, menu: &Menu) -> Option<usize> {
+        self.run_menu(menu, &|| {})
+    }
+
+    /// the option picked from a menu over a screen drawn by `screen`, which is
+    /// drawn again when the terminal is resized
+    pub fn choose_on(&self, menu: &Menu, screen: impl Fn()) -> Option<usize> {
+        screen();
+        self.run_menu(menu, &screen)
+    }
+
+    fn run_menu(&self, menu: &Menu, screen: &dyn Fn()) -> Option<usize> {
+        let mut offset = 0;
+
+        loop {
+            let shown = match menu.placement {
+                Placement::Box => self.draw_menu_box(menu, offset),
+                Placement::Prompt | Placement::Footer => {
+                    self.draw_menu_line(menu);
+                    menu.options.len()
+                }
+            };
+
+            let key = self.window.getch();
+            let picked = key.and_then(|key| {
+                menu.options
+                    .iter()
+                    .position(|(keys, _)| keys.contains(&key))
+            });
+            match (picked, key) {
+                (Some(index), _) => return Some(index),
+                (None, Some(Input::Character('\u{1b}'))) => return None,
+                (None, Some(Input::KeyResize)) => {
+                    // what was behind the menu was laid out for the old size
+                    pancurses::resize_term(0, 0);
+                    self.window.clear();
+                    screen();
+                }
+                (None, Some(Input::KeyNPage)) | (None, Some(Input::Character('>'))) => {
+                    offset = (offset + shown).min(menu.options.len() - shown)
+                }
+                (None, Some(Input::KeyPPage)) | (None, Some(Input::Character('<'))) => {
+                    offset = offset.saturating_sub(shown)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// draw a menu in a box over the middle of the map, starting at the option
+    /// at `offset`; returns how many options fit
+    fn draw_menu_box(&self, menu: &Menu, offset: usize) -> usize {
+        let layout = self.layout();
+        // the rest is scrolled to
+        let max_rows = (layout.height - 8).max(1) as usize;
+        let shown = menu.options.len().min(max_rows);
+        let scrolls = shown < menu.options.len();
+
+        let lines = menu
+            .options
+            .iter()
+            .map(|(keys, label)| {
+                let key = Menu::listed_key(keys).unwrap_or(' ');
+                format!("{} - {}", key, label)
+            })
+            .collect::<Vec<_>>();
+        let mut hint = String::from("Escape - cancel");
+        if scrolls {
+            hint += ", < > - scroll";
+        }
+
+        let inner_width = lines
+            .iter()
+            .chain([&menu.title, &hint])
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min((layout.width - 6).max(1) as usize) as i32;
+        let width = inner_width + 4;
+        let height = shown as i32 + 5;
+        let left = (layout.map_x + (layout.map_width - width) / 2).max(1);
+        let top = (layout.map_y + (layout.map_height - height) / 2).max(1);
+
+        for y in top..top + height {
+            let edge = y == top || y == top + height - 1;
+            for x in left..left + width {
+                let ch = match (edge, x == left || x == left + width - 1) {
+                    (true, true) => '+',
+                    (true, false) => '-',
+                    (false, true) => '|',
+                    (false, false) => ' ',
+                };
+                self.window.mvaddch(y, x, ch);
+            }
+        }
+        let fit = |line: &str| line.chars().take(inner_width as usize).collect::<String>();
+
+        self.window.attron(A_BOLD);
+        self.window.mvaddstr(top + 1, left + 2, fit(&menu.title));
+        self.window.attroff(A_BOLD);
+        for (i, line) in lines.iter().skip(offset).take(shown).enumerate() {
+            self.window
+                .mvaddstr(top + 2 + i as i32, left + 2, fit(line));
+        }
+        self.window.mvaddstr(top + height - 2, left + 2, fit(&hint));
+        self.window.refresh();
+        shown
+    }
+
+    /// draw a menu on one line, where its placement puts it
+    fn draw_menu_line(&self, menu: &Menu) {
+        let layout = self.layout();
+        if layout.is_too_small() {
+            return;
+        }
+        let (y, x, width) = match menu.placement {
+            Placement::Prompt => (layout.status_y - 1, 1, layout.stats_x - 2),
+            _ => (layout.bottom() - 1, 2, layout.width - 4),
+        };
+        let line = format!("{:<width$}", menu.line(), width = width as usize)
+            .chars()
+            .take(width as usize)
+            .collect::<String>();
+        self.window.mvaddstr(y, x, line);
+        self.window.refresh();
+    }
+
+    /// a yes or no question; Escape counts as no
+    pub fn ask_yes_no(&self, question: &str) -> bool {
+        let menu = Menu::new(question).keyed('y', "yes").keyed('n', "no");
+        self.choose(&menu) == Some(0)
+    }
+
+    /// read a line of text under a question, None if it was cancelled with Escape
+    pub fn ask_line(&self, question: &str, max_len: usize) -> Option<String> {
+        let mut line = String::new();
+        pancurses::curs_set(1);
+
+        let answer = loop {
+            self.window.clear();
+            self.window.mvaddstr(1, 2, question);
+            self.window.mvaddstr(3, 2, format!("> {}", line));
             self.window.mvaddstr(
-                STATUS_Y + 3,
-                PLAYER_STATS_X,
-                format!("Dungeon Level: {}", level),
+                self.layout().bottom() - 1,
+                2,
+                "Enter - done, Escape - cancel",
             );
+            self.window.mv(3, 4 + line.chars().count() as i32);
+            self.window.refresh();
 
-            self.window.color_set(pancurses::COLOR_WHITE);
-        } else {
-            player.alive = false;
-            player.ch = '%';
+            match self.window.getch() {
+                Some(Input::Character('\n')) | Some(Input::KeyEnter) => break Some(line),
+                Some(Input::Character('\u{1b}')) => break None,
+                Some(Input::KeyBackspace)
+                | Some(Input::Character('\u{7f}'))
+                | Some(Input::Character('\u{8}')) => {
+                    line.pop();
+                }
+                Some(Input::Character(c))
+                    if (c.is_alphanumeric() || c == ' ' || c == '-')
+                        && line.chars().count() < max_len =>
+                {
+                    line.push(c)
+                }
+                _ => {}
+            }
+        };
+
+        pancurses::curs_set(0);
+        answer.map(|line| line.trim().to_string())
+    }
 
-            self.window.color_set(pancurses::COLOR_RED);
+    /// list the best runs starting at a row, highlighting the one just recorded
+    pub fn draw_high_scores(&self, y: i32, entries: &[highscore::Entry], rank: Option<usize>) {
+        self.draw_colored(Color::Gold, || {
             self.window.attron(A_BOLD);
-            self.window
-                .mvaddstr(STATUS_Y - 2, PLAYER_STATS_X, "You're dead!");
+            self.window.mvaddstr(y, 2, "Top scores:");
             self.window.attroff(A_BOLD);
-            self.window.color_set(pancurses::COLOR_WHITE);
-        }
-    }
+        });
 
-    pub fn push_obj(&mut self, obj: Object) {
-        self.objects.borrow_mut().push(obj);
+        for (i, entry) in entries.iter().take(highscore::TOP_SHOWN).enumerate() {
+            if rank == Some(i) {
+                self.window.attron(A_BOLD);
+            }
+            self.window.mvaddstr(
+                y + 2 + i as i32,
+                2,
+                format!("{:>2}. {}", i + 1, entry)
+                    .chars()
+                    .take((self.layout().width - 4).max(0) as usize)
+                    .collect::<String>(),
+            );
+            if rank == Some(i) {
+                self.window.attroff(A_BOLD);
+            }
+        }
     }
 
-    fn draw_borders(&self) {
-        for i in 0..SCR_WIDTH {
+    fn draw_borders(&self, layout: &Layout) {
+        let (right, bottom) = (layout.width - 1, layout.bottom());
+        for i in 0..right {
             self.window.mvaddch(0, i, '-');
-            self.window.mvaddch(WINDOW_HEIGHT, i, '-');
+            self.window.mvaddch(bottom, i, '-');
         }
-        for i in 0..INV_X - 1 {
-            self.window.mvaddch(STATUS_Y - 3, i, '-');
+        for i in 0..layout.divider_x {
+            self.window.mvaddch(layout.status_y - 3, i, '-');
         }
 
-        for i in 0..WINDOW_HEIGHT {
+        for i in 0..bottom {
             self.window.mvaddch(i, 0, '|');
-            self.window.mvaddch(i, WINDOW_WIDTH, '|');
-            self.window.mvaddch(i, SCR_WIDTH, '|');
+            self.window.mvaddch(i, layout.divider_x, '|');
+            self.window.mvaddch(i, right, '|');
         }
 
         self.window.mvaddch(0, 0, '+');
-        self.window.mvaddch(WINDOW_HEIGHT, 0, '+');
-        self.window.mvaddch(0, WINDOW_WIDTH, '+');
-        self.window.mvaddch(0, SCR_WIDTH, '+');
-        self.window.mvaddch(WINDOW_HEIGHT, PLAYER_STATS_X - 1, '+');
-        self.window.mvaddch(WINDOW_HEIGHT, WINDOW_WIDTH, '+');
-        self.window.mvaddch(WINDOW_HEIGHT, SCR_WIDTH, '+');
+        self.window.mvaddch(bottom, 0, '+');
+        self.window.mvaddch(0, layout.divider_x, '+');
+        self.window.mvaddch(0, right, '+');
+        self.window.mvaddch(bottom, layout.stats_x - 1, '+');
+        self.window.mvaddch(bottom, layout.divider_x, '+');
+        self.window.mvaddch(bottom, right, '+');
+    }
+
+    /// A wall joined up with the known walls next to it, or `#` when line
+    /// drawing is turned off.
+    fn wall_glyph(&self, map: &Map, x: i32, y: i32) -> chtype {
+        if !self.line_walls {
+            return '#' as chtype;
+        }
+        match WallShape::at(map, x, y) {
+            WallShape::Plus => pancurses::ACS_PLUS(),
+            WallShape::VLine => pancurses::ACS_VLINE(),
+            WallShape::HLine => pancurses::ACS_HLINE(),
+            WallShape::ULCorner => pancurses::ACS_ULCORNER(),
+            WallShape::URCorner => pancurses::ACS_URCORNER(),
+            WallShape::LLCorner => pancurses::ACS_LLCORNER(),
+            WallShape::LRCorner => pancurses::ACS_LRCORNER(),
+            WallShape::LTee => pancurses::ACS_LTEE(),
+            WallShape::RTee => pancurses::ACS_RTEE(),
+            WallShape::TTee => pancurses::ACS_TTEE(),
+            WallShape::BTee => pancurses::ACS_BTEE(),
+        }
+    }
+
+    fn draw_too_small(&self, layout: &Layout) {
+        let lines = [
+            "The terminal is too small.".to_string(),
+            format!("It needs to be at least {}x{},", MIN_WIDTH, MIN_HEIGHT),
+            format!("but it is {}x{}.", layout.width, layout.height),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let x = ((layout.width - line.len() as i32) / 2).max(0);
+            let y = (layout.height / 2 - 1 + i as i32).max(0);
+            self.window.mvaddstr(y, x, line);
+        }
+        self.window.refresh();
     }
 }
 
@@ -205,20 +942,15 @@ impl Default for Graphics {
         pancurses::noecho();
         pancurses::start_color();
 
-        pancurses::init_pair(1, pancurses::COLOR_RED, pancurses::COLOR_BLACK);
-        pancurses::init_pair(2, pancurses::COLOR_GREEN, pancurses::COLOR_BLACK);
-        pancurses::init_pair(3, pancurses::COLOR_YELLOW, pancurses::COLOR_BLACK);
-        pancurses::init_pair(4, pancurses::COLOR_BLUE, pancurses::COLOR_BLACK);
-        pancurses::init_pair(5, pancurses::COLOR_MAGENTA, pancurses::COLOR_BLACK);
-        pancurses::init_pair(6, pancurses::COLOR_CYAN, pancurses::COLOR_BLACK);
-        pancurses::init_pair(7, pancurses::COLOR_WHITE, pancurses::COLOR_BLACK);
-
-        window.color_set(7);
+        let mut theme = Theme::default();
+        theme.apply();
 
         Self {
-            objects: RefCell::new(Vec::new()),
+            camera: Camera::default(),
+            look_at: None,
+            line_walls: true,
+            theme,
             window,
-            statuses: Vec::new(),
         }
     }
 }
@@ -228,3 +960,89 @@ impl Drop for Graphics {
         pancurses::endwin();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::Tile;
+
+    #[test]
+    fn menus_fit_on_one_line() {
+        let menu = Menu::new("")
+            .keyed('n', "write a note")
+            .keyed('q', "go back");
+        assert_eq!(menu.line(), "n - write a note, q - go back");
+
+        // only the first character key of an option is listed
+        let menu = Menu::new("In what direction?")
+            .keyed_by(&[Input::Character('k'), Input::KeyUp], "")
+            .keyed_by(&[Input::KeyDown], "")
+            .keyed_by(&[Input::KeyLeft, Input::Character('h')], "");
+        assert_eq!(menu.line(), "In what direction? k, h");
+    }
+
+    #[test]
+    fn the_layout_fits_the_terminal() {
+        let layout = Layout::new(80, 24);
+        assert!(!layout.is_too_small());
+        assert_eq!((layout.map_width, layout.map_height), (78, 11));
+        assert_eq!((layout.divider_x, layout.inventory_x), (79, None));
+        assert_eq!((layout.status_y, layout.stats_x), (15, 54));
+        assert_eq!(layout.message_lines(), 9);
+
+        // wide terminals get the inventory, tall ones a bigger map
+        let layout = Layout::new(120, 40);
+        assert_eq!((layout.map_width, layout.map_height), (87, 27));
+        assert_eq!((layout.divider_x, layout.inventory_x), (88, Some(90)));
+        assert_eq!(layout.message_lines(), 9);
+
+        assert!(Layout::new(79, 24).is_too_small());
+        assert!(Layout::new(80, 23).is_too_small());
+    }
+
+    #[test]
+    fn the_camera_keeps_its_target_in_the_middle() {
+        let map = vec![vec![Tile::wall(); 50]; 100];
+        let layout = Layout::new(80, 24);
+        let camera = Camera::follow(50, 25, &map, &layout);
+        assert_eq!((camera.x, camera.y), (11, 20));
+        assert_eq!(camera.to_screen(50, 25), Some((40, 6)));
+        assert_eq!(camera.to_screen(10, 25), None);
+
+        // the edges of the map stay at the edges of the view
+        let camera = Camera::follow(0, 0, &map, &layout);
+        assert_eq!((camera.x, camera.y), (0, 0));
+        let camera = Camera::follow(99, 49, &map, &layout);
+        assert_eq!((camera.x, camera.y), (22, 39));
+        assert_eq!(camera.to_screen(99, 49), Some((78, 11)));
+
+        let small = vec![vec![Tile::wall(); 5]; 20];
+        let camera = Camera::follow(0, 0, &small, &layout);
+        assert_eq!((camera.x, camera.y), (-29, -3));
+    }
+
+    #[test]
+    fn walls_join_up_with_the_walls_seen_next_to_them() {
+        // a room with a wall sticking into it from the top
+        let rows = ["#####", "#.#.#", "#...#", "#####"];
+        let mut map = vec![vec![Tile::empty(); rows.len()]; rows[0].len()];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                if ch == '#' {
+                    map[x][y] = Tile::wall();
+                }
+                map[x][y].visible = true;
+            }
+        }
+        assert_eq!(WallShape::at(&map, 0, 0), WallShape::ULCorner);
+        assert_eq!(WallShape::at(&map, 4, 3), WallShape::LRCorner);
+        assert_eq!(WallShape::at(&map, 2, 0), WallShape::TTee);
+        assert_eq!(WallShape::at(&map, 2, 1), WallShape::VLine);
+        assert_eq!(WallShape::at(&map, 1, 3), WallShape::HLine);
+        assert_eq!(WallShape::at(&map, 0, 1), WallShape::VLine);
+
+        // walls not seen yet don't count
+        map[1][0].visible = false;
+        assert_eq!(WallShape::at(&map, 0, 0), WallShape::VLine);
+    }
+}
@@ -47,7 +47,7 @@ pub fn raycast_on_map(map: &mut Map, px: i32, py: i32, points: &Vec<Point>) {
     let mut coords: Vec<Vec<Point>> = vec![];
 
     for point in points {
-        coords.push(line(px, py, point.x, point.y, &map));
+        coords.push(line(px, py, point.x, point.y, map));
     }
 
     // reset map visibility
@@ -1,448 +1,1212 @@
 use crate::ai;
-use crate::curses::{Graphics, Status, INV_X, PLAYER, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::ai::AI;
+use crate::command::{Command, Stat};
+use crate::curses::{self, Graphics, Menu, Status};
 use crate::fov;
+use crate::highscore;
+use crate::input;
 use crate::item;
 use crate::item::{Equipment, Item, Slot};
-use crate::object::{get_equipped_in_slot, move_by, Fighter, Object};
+use crate::keymap::Keymap;
+use crate::morgue;
+use crate::object;
+use crate::object::{
+    drop_loot, get_equipped_in_slot, move_by, DamageSource, Experience, Fighter, Hunger, Vitals,
+};
+use crate::options::Options;
+use crate::overview::{self, LevelNotes};
+use crate::pathfind;
+use crate::rng;
+use crate::save;
+use crate::shop;
+use crate::store::ObjectId;
+use crate::theme::Color;
 use crate::tile;
-use crate::tile::{Map, Tile, MAP_HEIGHT, MAP_WIDTH};
-use pancurses::Input;
+use crate::tile::{Map, Tile};
+use crate::world::World;
+use rand::Rng;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
 
 const PLAYER_DEF_HP: i32 = 40;
 const LEVEL_UP_BASE: i32 = 200;
 const LEVEL_UP_FACTOR: i32 = 150;
+const PLAYER_DEF_NUTRITION: i32 = 900;
+const POISON_DAMAGE: i32 = 1;
+const FAINT_CHANCE: u32 = 5; // one in five
+const FAINT_TURNS: u32 = 3;
+const REGEN_BASE_INTERVAL: u32 = 20;
+const REGEN_CON_FACTOR: i32 = 20;
+const REST_MAX_TURNS: u32 = 500;
+/// the longest a walk over many turns, such as exploring, goes on for
+const WALK_MAX_TURNS: u32 = 2000;
+const SCORE_PER_DEPTH: i32 = 50;
+/// one item for each letter
+const INVENTORY_SIZE: usize = 26;
+const SCORE_PER_KILL: i32 = 10;
 
+/// Everything about a run, and the rules it is played by. Drawing and asking
+/// the player things is left to the `Graphics` passed in by the front end, so
+/// commands can be executed without a terminal.
 pub struct Game {
     pub map: Map,
-    pub graphics: Graphics,
-    pub inventory: Vec<Object>,
+    pub world: RefCell<World>,
+    /// the messages of this turn, waiting to be drawn
+    pub statuses: Vec<Status>,
+    /// every message that has scrolled by, oldest first
+    pub log: Vec<String>,
     pub dungeon_level: u32,
+    pub turns: u32,
+    pub kills: HashMap<String, u32>,
+    pub seed: u64,
+    pub options: Options,
+    pub keymap: Keymap,
+    /// the levels visited so far, the current one last
+    pub levels: Vec<LevelNotes>,
 }
 
 impl Game {
-    pub fn start(&mut self) {
-        let mut player = Object::new(
-            WINDOW_WIDTH / 2,
-            WINDOW_HEIGHT / 2,
-            '@',
-            pancurses::COLOR_WHITE,
-            true,
-            "player",
-            true,
-        );
+    /// throw away whatever run was loaded and play a fresh one
+    pub fn new_game(&mut self, graphics: &mut Graphics, name: &str) {
+        self.begin(name);
+        self.play(graphics);
+    }
 
-        player.alive = true;
+    /// throw away whatever run was loaded and set up a fresh one
+    pub fn begin(&mut self, name: &str) {
+        *self.world.borrow_mut() = World::default();
+        self.statuses.clear();
+        self.log.clear();
+        self.dungeon_level = 1;
+        self.turns = 0;
+        self.kills.clear();
+        self.levels = vec![LevelNotes::new(1, 0)];
+        self.seed = rng::random_seed();
+        rng::seed(self.seed);
 
-        player.fighter = Some(Fighter {
-            base_max_hp: PLAYER_DEF_HP,
-            hp: PLAYER_DEF_HP,
-            base_defence: 1,
-            xp: 0,
-            base_power: 4,
-        });
+        let mut world = self.world.borrow_mut();
+        // make_map puts the player in the first room
+        spawn_player(&mut world, name);
 
-        let mut dagger = Object::new(0, 0, '-', pancurses::COLOR_BLUE, false, "dagger", false);
-        dagger.item = Some(Item::Sword);
-        dagger.equipment = Some(Equipment {
-            equipped: true,
-            slot: Slot::LeftHand,
-            max_hp_bonus: 0,
-            defense_bonus: 0,
-            power_bonus: 2,
-        });
-        self.inventory.push(dagger);
+        // procedurally generate the map
+        self.map = tile::make_map(&mut world, self.dungeon_level);
+    }
 
-        player.level_up_xp = LEVEL_UP_BASE + player.level * LEVEL_UP_FACTOR;
+    /// pick up a saved run
+    pub fn continue_game(&mut self, graphics: &mut Graphics) -> io::Result<()> {
+        save::load(self)?;
+        // the generator's state isn't saved, so carry on from a seed that is still
+        // the same every time this save is loaded
+        rng::seed(self.seed.wrapping_add(u64::from(self.turns)));
+        let name = {
+            let world = self.world.borrow();
+            world.name(world.player_id()).to_string()
+        };
+        self.add_status(format!("Welcome back, {}.", name), 1);
 
-        self.graphics.push_obj(player);
+        self.play(graphics);
+        Ok(())
+    }
 
-        // procedurally generate the map
-        self.map = tile::make_map(&mut self.graphics.objects.borrow_mut(), self.dungeon_level);
+    /// the main loop, until the player dies or quits
+    fn play(&mut self, graphics: &mut Graphics) {
+        loop {
+            let names = self.get_names_under_player();
+            if !names.is_empty() {
+                self.add_status(names, 1)
+            };
+
+            self.update_fov();
+            self.draw(graphics);
+
+            if self.can_level_up() {
+                let stat = input::choose_stat(graphics);
+                self.execute(Command::LevelUp(stat));
+            }
+
+            let player_action = match input::read_command(self, graphics) {
+                Some(command) => self.execute(command),
+                None => PlayerAction::DidntTakeTurn,
+            };
+
+            match player_action {
+                PlayerAction::Exit => match save::write(self) {
+                    Ok(()) => break,
+                    Err(e) => {
+                        // the game goes on, but trying to save doesn't take a turn
+                        self.add_status(format!("Couldn't save the game: {}", e), 1);
+                        continue;
+                    }
+                },
+                PlayerAction::Abandon => {
+                    let line = match morgue::write(self, "quit") {
+                        Ok(path) => format!("Your character dump is in {}.", path.display()),
+                        Err(e) => format!("Couldn't write a character dump: {}", e),
+                    };
+                    graphics.show_text_screen("You quit the game.", &[line]);
+                    break;
+                }
+                _ => {}
+            }
+
+            if self.player_alive() && player_action != PlayerAction::DidntTakeTurn {
+                self.monsters_take_turn();
+                self.pass_time();
+            }
+
+            drop_loot(&mut self.world.borrow_mut(), &mut self.statuses);
+
+            if !self.player_alive() {
+                self.end_game(graphics);
+                break;
+            }
+        }
+    }
+
+    /// the map, the messages, the stats and the inventory
+    pub fn draw(&mut self, graphics: &mut Graphics) {
+        graphics.draw(&self.map, &self.world.borrow(), &self.statuses);
+        graphics.draw_player_stats(&self.world.borrow(), self.dungeon_level);
+        self.show_inventory(graphics);
+        if !graphics.layout().is_too_small() {
+            curses::age_statuses(&mut self.statuses, &mut self.log);
+        }
+    }
+
+    pub fn add_status(&mut self, msg: String, rounds: u32) {
+        self.statuses.push(Status::new(msg, rounds));
+    }
 
+    /// the player died: record the run, then show the death screen until they quit
+    fn end_game(&mut self, graphics: &mut Graphics) {
+        let (name, cause, level, gold) = {
+            let world = self.world.borrow();
+            let player_id = world.player_id();
+            let cause = world
+                .killed_by
+                .get(player_id)
+                .map_or("died".to_string(), |source| source.to_string());
+            let gold = world.gold.get(player_id).copied().unwrap_or(0);
+            let level = world.experience[player_id].level;
+            (world.name(player_id).to_string(), cause, level, gold)
+        };
+        let kills: u32 = self.kills.values().sum();
+
+        let mut summary = vec![
+            format!(
+                "{} {} on dungeon level {}.",
+                name, cause, self.dungeon_level
+            ),
+            format!(
+                "You survived for {} turns and reached character level {}.",
+                self.turns, level
+            ),
+            format!("You collected {} gold and killed {} monsters.", gold, kills),
+            format!("Final score: {}", self.score()),
+        ];
+
+        let entry = highscore::Entry::new(self.score(), &name, self.dungeon_level, level, &cause);
+        let rank = match highscore::record(&entry) {
+            Ok(rank) => rank,
+            Err(e) => {
+                summary.push(format!("Couldn't save your high score: {}", e));
+                None
+            }
+        };
+        let high_scores = highscore::load();
+
+        match morgue::write(self, &cause) {
+            Ok(path) => summary.push(format!("Your character dump is in {}.", path.display())),
+            Err(e) => summary.push(format!("Couldn't write a character dump: {}", e)),
+        }
+
+        let menu = Menu::new("")
+            .keyed('m', "view the map")
+            .keyed('i', "inventory")
+            .keyed('l', "message log")
+            .keyed('q', "back to the menu")
+            .footer();
+        loop {
+            let picked = graphics.choose_on(&menu, || {
+                graphics.draw_death_screen(&summary, &high_scores, rank)
+            });
+            match picked {
+                Some(0) => {
+                    self.add_status(
+                        "This is where you died. Press any key to go back.".to_string(),
+                        1,
+                    );
+                    self.draw(graphics);
+                    graphics.window.getch();
+                }
+                Some(1) => {
+                    let world = self.world.borrow();
+                    let mut lines = vec![];
+                    for (i, &item) in world.inventory(world.player_id()).iter().enumerate() {
+                        let label = describe_item(&world, item);
+                        lines.push(format!("{} - {}", (i + 97) as u8 as char, label));
+                    }
+                    drop(world);
+                    graphics.show_text_screen("Inventory", &lines);
+                }
+                Some(2) => graphics.show_log(&self.log),
+                _ => break,
+            }
+        }
+    }
+
+    fn update_fov(&mut self) {
+        let (width, height) = (tile::width(&self.map), tile::height(&self.map));
         let mut points = vec![];
-        for i in 0..MAP_WIDTH {
+        for i in 0..width {
             points.push(fov::Point { x: i, y: 0 });
             points.push(fov::Point {
                 x: i,
-                y: MAP_HEIGHT,
+                y: height - 1,
             });
         }
 
-        for i in 0..MAP_HEIGHT {
+        for i in 0..height {
             points.push(fov::Point { x: 0, y: i });
-            points.push(fov::Point { x: MAP_WIDTH, y: i });
+            points.push(fov::Point { x: width - 1, y: i });
         }
 
-        loop {
-            let names = self.get_names_under_player();
-            if !names.is_empty() {
-                self.graphics.add_status(names, 1)
-            };
+        let (x, y) = {
+            let world = self.world.borrow();
+            world.pos(world.player_id())
+        };
+        fov::raycast_on_map(&mut self.map, x, y, &points);
 
-            {
-                let player = &self.graphics.objects.borrow()[PLAYER];
-                fov::raycast_on_map(&mut self.map, player.x, player.y, &points);
+        // remember what lies on the tiles in view; monsters move, so only
+        // things that can't are remembered
+        for tile in self.map.iter_mut().flatten() {
+            if tile.currently_visible {
+                tile.remembered = None;
             }
+        }
+        let world = self.world.borrow();
+        for (id, renderable) in world.renderables.iter() {
+            if world.is_alive(id) {
+                continue;
+            }
+            if let Some(position) = world.positions.get(id) {
+                let tile = &mut self.map[position.x as usize][position.y as usize];
+                if tile.currently_visible {
+                    tile.remembered = Some(*renderable);
+                }
+            }
+        }
+    }
 
-            self.graphics.draw(&self.map);
+    fn player_alive(&self) -> bool {
+        let world = self.world.borrow();
+        world.is_alive(world.player_id())
+    }
 
-            self.graphics.draw_player_stats(
-                &mut self.graphics.objects.borrow_mut()[PLAYER],
-                self.dungeon_level,
-                &self.inventory,
-            );
+    /// returns the name of a monster the player can currently see, if any
+    fn monster_in_view(&self) -> Option<String> {
+        let world = self.world.borrow();
+        let name = world
+            .ais
+            .iter()
+            .find(|(id, ai)| {
+                let (x, y) = world.pos(*id);
+                world.is_alive(*id)
+                    && !matches!(ai, AI::Shopkeeper { .. })
+                    && self.map[x as usize][y as usize].currently_visible
+            })
+            .map(|(id, _)| world.name(id).to_string());
+        name
+    }
 
-            self.show_inventory();
+    fn monsters_take_turn(&mut self) {
+        // whatever spawns during the turn waits for the next one, and whatever
+        // is removed doesn't get to act
+        let ids = self.world.borrow().ais.ids();
+        for id in ids {
+            ai::take_turn(id, self);
+        }
+    }
 
-            self.level_up();
+    /// digest food, suffer from poison and regenerate, once per player turn
+    fn pass_time(&mut self) {
+        // whatever killed the player this turn, time stops for them
+        if !self.player_alive() {
+            return;
+        }
+        self.turns += 1;
 
-            let player_action = self.handle_keys();
+        let hunger = {
+            let mut world = self.world.borrow_mut();
+            let player_id = world.player_id();
+            let vitals = &mut world.vitals[player_id];
+            let before = vitals.hunger();
+            vitals.nutrition -= 1;
+            let after = vitals.hunger();
 
-            if let PlayerAction::Exit = player_action {
-                break;
+            if before != after {
+                let (msg, color) = match after {
+                    Hunger::NotHungry => ("You are no longer hungry.", Color::Good),
+                    Hunger::Hungry => ("You are beginning to feel hungry.", Color::Warning),
+                    Hunger::Weak => ("You feel weak from hunger.", Color::Warning),
+                    Hunger::Fainting => ("You are fainting from lack of food!", Color::Danger),
+                    Hunger::Starved => ("You die from starvation.", Color::Danger),
+                };
+                self.statuses
+                    .push(Status::new(msg.to_string(), 1).with_color(color));
             }
 
-            if self.graphics.objects.borrow()[PLAYER].alive
-                && player_action != PlayerAction::DidntTakeTurn
-            {
-                let m = self.graphics.objects.borrow().len();
-                for id in 0..m {
-                    if self.graphics.objects.borrow()[id].ai.is_some() {
-                        ai::take_turn(id, self);
-                    }
+            let statuses = &mut self.statuses;
+            if after == Hunger::Starved {
+                let hp = world.fighters.get(player_id).map_or(0, |f| f.hp);
+                object::take_damage(
+                    &mut world,
+                    player_id,
+                    hp,
+                    DamageSource::Starvation,
+                    statuses,
+                );
+            }
+
+            if world.vitals[player_id].poisoned > 0 {
+                world.vitals[player_id].poisoned -= 1;
+                object::take_damage(
+                    &mut world,
+                    player_id,
+                    POISON_DAMAGE,
+                    DamageSource::Poison,
+                    statuses,
+                );
+                if world.vitals[player_id].poisoned == 0 {
+                    statuses.push(
+                        Status::new("You feel less sick.".to_string(), 1).with_color(Color::Good),
+                    );
+                }
+            }
+
+            if self.turns.is_multiple_of(regen_interval(&world)) && can_regenerate(&world) {
+                let max_hp = object::max_hp(&world, player_id);
+                if world.fighters.get(player_id).is_some_and(|f| f.hp < max_hp) {
+                    object::heal(&mut world, player_id, 1);
                 }
             }
+
+            after
+        };
+
+        if hunger == Hunger::Fainting && rng::get().gen_range(0, FAINT_CHANCE) == 0 {
+            // the monsters get a few free turns while the player is passed out
+            self.statuses.push(
+                Status::new("You faint from lack of food.".to_string(), 1)
+                    .with_color(Color::Danger),
+            );
+            for _ in 0..FAINT_TURNS {
+                self.monsters_take_turn();
+            }
         }
     }
 
-    fn get_names_under_player(&self) -> String {
-        let objs = self.graphics.objects.borrow();
-        let (px, py) = objs[PLAYER].pos();
+    /// rest until healed, or until something interesting happens
+    fn rest(&mut self) -> PlayerAction {
+        if let Some(name) = self.monster_in_view() {
+            self.add_status(format!("You cannot rest with the {} nearby.", name), 1);
+            return PlayerAction::DidntTakeTurn;
+        }
 
-        let names = objs
-            .iter()
-            .filter(|obj| obj.pos() == (px, py) && obj.name != "player")
-            .map(|obj| obj.name.clone())
-            .collect::<Vec<_>>();
+        for _ in 0..REST_MAX_TURNS {
+            let (hp, base_max_hp, hunger, regenerates) = {
+                let world = self.world.borrow();
+                let player_id = world.player_id();
+                let fighter = world.fighters[player_id];
+                let hunger = world.vitals[player_id].hunger();
+                (
+                    fighter.hp,
+                    fighter.base_max_hp,
+                    hunger,
+                    can_regenerate(&world),
+                )
+            };
+            // heal() never goes past the base maximum, so don't wait for more than that
+            if hp >= base_max_hp {
+                self.add_status("You feel rested.".to_string(), 1);
+                break;
+            }
+            if !regenerates {
+                self.add_status("You are in no condition to rest.".to_string(), 1);
+                break;
+            }
 
-        names.join(", ")
+            self.monsters_take_turn();
+            self.pass_time();
+            self.update_fov();
+
+            {
+                let world = self.world.borrow();
+                let player_id = world.player_id();
+                if !world.is_alive(player_id) || world.fighters[player_id].hp < hp {
+                    break;
+                }
+                if world.vitals[player_id].hunger() != hunger {
+                    break;
+                }
+            }
+            if let Some(name) = self.monster_in_view() {
+                self.statuses.push(
+                    Status::new(format!("You stop resting. A {} comes into view.", name), 1)
+                        .with_color(Color::Warning),
+                );
+                break;
+            }
+        }
+
+        PlayerAction::DidntTakeTurn
     }
 
-    pub fn handle_keys(&mut self) -> PlayerAction {
-        let is_alive = self.graphics.objects.borrow()[PLAYER].alive;
-        match (self.graphics.window.getch(), is_alive) {
-            (Some(Input::KeyDC), _) | (Some(Input::Character('q')), _) => PlayerAction::Exit, // exit game
+    /// walk to the nearest place not seen yet, and to the items seen on the
+    /// way if the options say so, until something interesting happens
+    fn explore(&mut self) -> PlayerAction {
+        if let Some(name) = self.monster_in_view() {
+            self.add_status(format!("You cannot explore with the {} nearby.", name), 1);
+            return PlayerAction::DidntTakeTurn;
+        }
 
-            (Some(Input::Character(',')), true) => {
-                // let objs = self.graphics.objects.borrow();
-                let item_id = self.graphics.objects.borrow().iter().position(|object| {
-                    object.pos() == self.graphics.objects.borrow()[PLAYER].pos()
-                        && object.item.is_some()
-                });
+        for _ in 0..WALK_MAX_TURNS {
+            if let Some(item) = self.item_to_collect() {
+                self.pick_item_up(item);
+                if !self.spend_walk_turn("exploring") {
+                    return PlayerAction::DidntTakeTurn;
+                }
+                continue;
+            }
 
-                if let Some(item_id) = item_id {
-                    self.pick_item_up(item_id);
+            match self.explore_step() {
+                Some((dx, dy)) => {
+                    if !self.walk_step(dx, dy, "exploring") {
+                        return PlayerAction::DidntTakeTurn;
+                    }
+                }
+                None => {
+                    self.explored();
+                    break;
                 }
-                PlayerAction::TookTurn
             }
+        }
 
-            // rest, do nothing for a turn
-            (Some(Input::Character('.')), true) => PlayerAction::TookTurn,
+        PlayerAction::DidntTakeTurn
+    }
 
-            // apply (use) an item
-            (Some(Input::Character('a')), true) => self.apply_item(),
+    /// the first step towards what exploring goes to next, None if there is
+    /// nowhere left to go
+    fn explore_step(&self) -> Option<(i32, i32)> {
+        let world = self.world.borrow();
+        let collect = self.options.explore_pickup
+            && world.inventory(world.player_id()).len() < INVENTORY_SIZE;
+        let auto_pickup_gold = self.options.auto_pickup_gold;
+        pathfind::first_step(&self.map, &world, |x, y| {
+            pathfind::is_frontier(x, y, &self.map)
+                || (collect
+                    && self.map[x as usize][y as usize].remembered.is_some()
+                    && world
+                        .at(x, y)
+                        .any(|id| is_collectable(&world, id, auto_pickup_gold)))
+        })
+    }
 
-            (Some(Input::Character('d')), true) => self.drop_item(),
+    /// whether exploring has nothing left to do on this level
+    pub fn is_explored(&self) -> bool {
+        self.item_to_collect().is_none() && self.explore_step().is_none()
+    }
 
-            // (Some(Input::Character('D')), true) => self.drop_item_by_type(),
-            (Some(Input::Character('>')), true) => {
-                let player_on_stairs = self.graphics.objects.borrow().iter().any(|object| {
-                    object.pos() == self.graphics.objects.borrow()[PLAYER].pos()
-                        && object.name == "stairs"
-                });
+    /// the item underfoot, if exploring should pick it up
+    fn item_to_collect(&self) -> Option<ObjectId> {
+        if !self.options.explore_pickup {
+            return None;
+        }
+        let world = self.world.borrow();
+        let player_id = world.player_id();
+        if world.inventory(player_id).len() >= INVENTORY_SIZE {
+            return None;
+        }
+        let (x, y) = world.pos(player_id);
+        let item = world
+            .at(x, y)
+            .find(|&id| world.items.contains(id) && is_collectable(&world, id, false));
+        item
+    }
 
-                if player_on_stairs {
-                    self.next_level();
-                }
+    /// nothing is left to explore; exploring again offers the way to the
+    /// stairs if they were found
+    fn explored(&mut self) {
+        let stairs = self.known_stairs();
+        let player = {
+            let world = self.world.borrow();
+            world.pos(world.player_id())
+        };
+        let msg = match stairs {
+            None => "Nothing is left to explore, but you haven't found the stairs.",
+            Some(stairs) if stairs == player => "Nothing is left to explore.",
+            Some(_) => "Nothing is left to explore. Explore again to head for the stairs.",
+        };
+        self.add_status(msg.to_string(), 1);
+    }
+
+    /// where the stairs are, if the player has seen them
+    pub fn known_stairs(&self) -> Option<(i32, i32)> {
+        let world = self.world.borrow();
+        let stairs = world
+            .positions
+            .iter()
+            .find(|(id, position)| {
+                world.stairs.contains(*id)
+                    && self.map[position.x as usize][position.y as usize].visible
+            })
+            .map(|(_, position)| (position.x, position.y));
+        stairs
+    }
 
-                PlayerAction::DidntTakeTurn
+    /// walk to a tile the player has seen, until there or until something
+    /// interesting happens
+    fn travel(&mut self, x: i32, y: i32) -> PlayerAction {
+        if !tile::in_bounds(x, y, &self.map) || !self.map[x as usize][y as usize].visible {
+            self.add_status("You don't know what is there.".to_string(), 1);
+            return PlayerAction::DidntTakeTurn;
+        }
+        if let Some(name) = self.monster_in_view() {
+            self.add_status(format!("You cannot travel with the {} nearby.", name), 1);
+            return PlayerAction::DidntTakeTurn;
+        }
+
+        for _ in 0..WALK_MAX_TURNS {
+            let step = {
+                let world = self.world.borrow();
+                pathfind::first_step(&self.map, &world, |goal_x, goal_y| {
+                    (goal_x, goal_y) == (x, y)
+                })
+            };
+            match step {
+                Some((dx, dy)) if self.walk_step(dx, dy, "travelling") => {}
+                Some(_) => break,
+                None => {
+                    let arrived = {
+                        let world = self.world.borrow();
+                        world.pos(world.player_id()) == (x, y)
+                    };
+                    if !arrived {
+                        self.add_status("You don't know a way there.".to_string(), 1);
+                    }
+                    break;
+                }
             }
+        }
+
+        PlayerAction::DidntTakeTurn
+    }
+
+    /// Move in a direction until something comes up. Through rooms that is
+    /// a straight line, ending at walls, at doorways on either side and where
+    /// the room is left; along corridors it follows the bends, ending at
+    /// forks and at the rooms they lead into. Anything lying on the way, and
+    /// any monster coming into view, ends it too.
+    fn run(&mut self, dx: i32, dy: i32) -> PlayerAction {
+        let (mut dx, mut dy) = (dx, dy);
+        let mut previous = {
+            let world = self.world.borrow();
+            world.pos(world.player_id())
+        };
 
-            // movement keys
-            (Some(Input::KeyUp), true) | (Some(Input::Character('k')), true) => {
-                self.player_move_or_attack(0, -1);
-                PlayerAction::TookTurn
+        for _ in 0..WALK_MAX_TURNS {
+            if !self.walk_step(dx, dy, "running") {
+                break;
             }
-            (Some(Input::KeyDown), true) | (Some(Input::Character('j')), true) => {
-                self.player_move_or_attack(0, 1);
-                PlayerAction::TookTurn
+            let world = self.world.borrow();
+            let player_id = world.player_id();
+            let (x, y) = world.pos(player_id);
+            if world.at(x, y).any(|id| id != player_id) {
+                break;
             }
-            (Some(Input::KeyLeft), true) | (Some(Input::Character('h')), true) => {
-                self.player_move_or_attack(-1, 0);
-                PlayerAction::TookTurn
+
+            let map = &self.map;
+            let walkable = |x: i32, y: i32| {
+                tile::in_bounds(x, y, map)
+                    && map[x as usize][y as usize].visible
+                    && !map[x as usize][y as usize].blocked
+            };
+            // tiles in a room have open floor around them, a corridor is a
+            // single tile wide
+            let in_room = |x: i32, y: i32| {
+                [(-1, -1), (0, -1), (-1, 0), (0, 0)]
+                    .iter()
+                    .any(|(left, top)| {
+                        (0..2).all(|i| (0..2).all(|j| walkable(x + left + i, y + top + j)))
+                    })
+            };
+            // what is beside the way, to notice openings
+            let sides = |x: i32, y: i32| (walkable(x - dy, y + dx), walkable(x + dy, y - dx));
+
+            match (in_room(previous.0, previous.1), in_room(x, y)) {
+                // walked through a doorway one way or the other
+                (false, true) | (true, false) => break,
+                (true, true) => {
+                    if !walkable(x + dx, y + dy) || sides(x, y) != sides(previous.0, previous.1) {
+                        break;
+                    }
+                }
+                (false, false) => {
+                    let near = |a: (i32, i32), b: (i32, i32)| {
+                        (a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1
+                    };
+                    let around = (-1..=1)
+                        .flat_map(|i| (-1..=1).map(move |j| (x + i, y + j)))
+                        .filter(|&tile| {
+                            tile != (x, y) && tile != previous && walkable(tile.0, tile.1)
+                        })
+                        .collect::<Vec<_>>();
+                    // the ways on, leaving out what could be reached from the
+                    // last tile already; a corner cut short is all there is
+                    // to those sometimes
+                    let mut open = around
+                        .iter()
+                        .copied()
+                        .filter(|&tile| !near(tile, previous))
+                        .collect::<Vec<_>>();
+                    if open.is_empty() {
+                        open = around;
+                    }
+                    // a fork has ways out that aren't next to each other
+                    let fork = open.iter().any(|&a| open.iter().any(|&b| !near(a, b)));
+                    if open.is_empty() || fork {
+                        break;
+                    }
+                    // straight on if possible, else around the bend,
+                    // preferring not to cut corners
+                    let next = open
+                        .iter()
+                        .copied()
+                        .find(|&tile| tile == (x + dx, y + dy))
+                        .or_else(|| open.iter().copied().find(|&(i, j)| i == x || j == y))
+                        .unwrap_or(open[0]);
+                    (dx, dy) = (next.0 - x, next.1 - y);
+                }
             }
-            (Some(Input::KeyRight), true) | (Some(Input::Character('l')), true) => {
-                self.player_move_or_attack(1, 0);
-                PlayerAction::TookTurn
+            previous = (x, y);
+        }
+
+        PlayerAction::DidntTakeTurn
+    }
+
+    /// one step of a walk over many turns; false once the walk should stop
+    fn walk_step(&mut self, dx: i32, dy: i32, activity: &str) -> bool {
+        let position = {
+            let world = self.world.borrow();
+            world.pos(world.player_id())
+        };
+        self.player_move_or_attack(dx, dy);
+        let moved = {
+            let world = self.world.borrow();
+            world.pos(world.player_id()) != position
+        };
+        self.spend_walk_turn(activity) && moved
+    }
+
+    /// let the rest of the dungeon have its turn during a walk; false if
+    /// something happened that is worth stopping for
+    fn spend_walk_turn(&mut self, activity: &str) -> bool {
+        let hp = {
+            let world = self.world.borrow();
+            world.fighters[world.player_id()].hp
+        };
+        self.monsters_take_turn();
+        self.pass_time();
+        self.update_fov();
+
+        {
+            let world = self.world.borrow();
+            let player_id = world.player_id();
+            if !world.is_alive(player_id) || world.fighters[player_id].hp < hp {
+                return false;
             }
+        }
+        if let Some(name) = self.monster_in_view() {
+            self.statuses.push(
+                Status::new(
+                    format!("You stop {}. A {} comes into view.", activity, name),
+                    1,
+                )
+                .with_color(Color::Warning),
+            );
+            return false;
+        }
+        true
+    }
+
+    fn get_names_under_player(&self) -> String {
+        let world = self.world.borrow();
+        let player_id = world.player_id();
+        let (px, py) = world.pos(player_id);
+
+        let names = world
+            .at(px, py)
+            .filter(|id| *id != player_id)
+            .map(|id| {
+                if world.for_sale.contains(id) {
+                    format!(
+                        "{} (for sale, {} gold)",
+                        world.name(id),
+                        shop::price(&world, id)
+                    )
+                } else {
+                    world.name(id).to_string()
+                }
+            })
+            .collect::<Vec<_>>();
 
-            (Some(_), _) | (None, _) => PlayerAction::DidntTakeTurn,
+        names.join(", ")
+    }
+
+    /// carry out a command, wherever it came from
+    pub fn execute(&mut self, command: Command) -> PlayerAction {
+        // the dead can only quit
+        if !self.player_alive() && !matches!(command, Command::Quit | Command::Abandon) {
+            return PlayerAction::DidntTakeTurn;
         }
+        match command {
+            Command::Quit => PlayerAction::Exit,
+            Command::Abandon => PlayerAction::Abandon,
+            // a single step, however far the command asks for
+            Command::Move(dx, dy) => self.player_move_or_attack(dx.signum(), dy.signum()),
+            Command::Run(dx, dy) => self.run(dx.signum(), dy.signum()),
+            Command::Travel(x, y) => self.travel(x, y),
+            Command::Pickup => self.pick_up(),
+            Command::Wait => PlayerAction::TookTurn,
+            Command::Rest => self.rest(),
+            Command::Explore => self.explore(),
+            Command::Apply {
+                slot,
+                direction,
+                target,
+            } => self.apply_item(slot, direction, target),
+            Command::Drop(slot) => self.drop_item(slot),
+            Command::Buy(slot) => shop::buy(slot, self),
+            Command::Sell(slot) => shop::sell(slot, self),
+            Command::Descend => self.descend(),
+            Command::LevelUp(stat) => self.level_up(stat),
+        }
+    }
+
+    fn pick_up(&mut self) -> PlayerAction {
+        let item_id = {
+            let world = self.world.borrow();
+            let (x, y) = world.pos(world.player_id());
+            let item_id = world.at(x, y).find(|id| world.items.contains(*id));
+            item_id
+        };
+
+        if let Some(item_id) = item_id {
+            self.pick_item_up(item_id);
+        } else {
+            self.pick_gold_up();
+        }
+        PlayerAction::TookTurn
+    }
+
+    fn descend(&mut self) -> PlayerAction {
+        let player_on_stairs = {
+            let world = self.world.borrow();
+            let (x, y) = world.pos(world.player_id());
+            let on_stairs = world.at(x, y).any(|id| world.stairs.contains(id));
+            on_stairs
+        };
+
+        if player_on_stairs {
+            self.next_level();
+        }
+
+        PlayerAction::DidntTakeTurn
     }
 
     /// add to the player's inventory and remove from the map
-    pub fn pick_item_up(&mut self, object_id: usize) {
-        if self.inventory.len() >= 26 {
-            self.graphics.add_status(
-                format!(
-                    "Your inventory is full, cannot pick up {}.",
-                    self.graphics.objects.borrow()[object_id].name
-                ),
+    pub fn pick_item_up(&mut self, object_id: ObjectId) {
+        let mut world = self.world.borrow_mut();
+        let player_id = world.player_id();
+        let name = world.name(object_id).to_string();
+        if world.inventory(player_id).len() >= INVENTORY_SIZE {
+            self.statuses.push(Status::new(
+                format!("Your inventory is full, cannot pick up {}.", name),
                 1,
-            );
+            ));
         } else {
-            let item = self.graphics.objects.borrow_mut().swap_remove(object_id);
-            self.graphics
-                .add_status(format!("You picked up a {}!", item.name), 1);
-            let index = self.inventory.len();
-            let slot = item.equipment.map(|e| e.slot);
-            self.inventory.push(item);
+            self.statuses
+                .push(Status::new(format!("You picked up a {}!", name), 1));
+            if world.for_sale.contains(object_id) {
+                self.statuses.push(Status::new(
+                    format!("\"For you, only {} gold.\"", shop::price(&world, object_id)),
+                    1,
+                ));
+            }
+            world.give(player_id, object_id);
 
             // automatically equip, if the corresponding equipment slot is unused
-            if let Some(slot) = slot {
-                if get_equipped_in_slot(slot, &self.inventory).is_none() {
-                    self.inventory[index].equip(&mut self.graphics.statuses);
+            if let Some(slot) = world.equipment.get(object_id).map(|e| e.slot) {
+                if get_equipped_in_slot(&world, player_id, slot).is_none() {
+                    object::equip(&mut world, object_id, &mut self.statuses);
                 }
             }
         }
     }
 
-    pub fn player_move_or_attack(&mut self, dx: i32, dy: i32) {
-        // the coordinates the player is moving to/attacking
-        let x = self.graphics.objects.borrow()[PLAYER].x + dx;
-        let y = self.graphics.objects.borrow()[PLAYER].y + dy;
-
-        // try to find an attackable object there
-        let target_id = self
-            .graphics
-            .objects
-            .borrow_mut()
-            .iter()
-            .position(|object| object.pos() == (x, y) && object.alive);
+    pub fn player_move_or_attack(&mut self, dx: i32, dy: i32) -> PlayerAction {
+        // try to find an attackable object where the player is moving to
+        let (target_id, is_shopkeeper) = {
+            let world = self.world.borrow();
+            let (x, y) = world.pos(world.player_id());
+            let target_id = world.at(x + dx, y + dy).find(|id| world.is_alive(*id));
+            let is_shopkeeper = target_id
+                .is_some_and(|id| matches!(world.ais.get(id), Some(AI::Shopkeeper { .. })));
+            (target_id, is_shopkeeper)
+        };
 
         // attack if target found, move otherwise
         match target_id {
+            Some(target_id) if is_shopkeeper => {
+                // peaceful shopkeepers are traded with, not attacked
+                return shop::greet(target_id, self);
+            }
             Some(target_id) => {
-                let mut objs = self.graphics.objects.borrow_mut();
-                let (player, mut target) = ai::mut_two(PLAYER, target_id, &mut objs);
-
-                player.attack(&mut target, &mut self.graphics.statuses, &self.inventory);
+                let mut world = self.world.borrow_mut();
+                let player_id = world.player_id();
+                let name = world.name(target_id).to_string();
+                if object::attack(&mut world, player_id, target_id, &mut self.statuses) {
+                    *self.kills.entry(name).or_insert(0) += 1;
+                }
             }
             None => {
-                move_by(
-                    PLAYER,
-                    dx,
-                    dy,
-                    &self.map,
-                    &mut self.graphics.objects.borrow_mut(),
-                );
+                {
+                    let mut world = self.world.borrow_mut();
+                    let player_id = world.player_id();
+                    move_by(&mut world, player_id, dx, dy, &self.map);
+                }
+                if self.options.auto_pickup_gold {
+                    self.pick_gold_up();
+                }
             }
         }
+        PlayerAction::TookTurn
+    }
+
+    /// put a pile of gold the player stands on in the purse
+    fn pick_gold_up(&mut self) {
+        let mut world = self.world.borrow_mut();
+        let player_id = world.player_id();
+        let (x, y) = world.pos(player_id);
+        // the player carries a purse too, so only look at loose piles
+        let pile = world
+            .at(x, y)
+            .find(|id| world.gold.contains(*id) && !world.fighters.contains(*id));
+        if let Some(pile) = pile {
+            let amount = world.gold[pile];
+            let name = world.name(pile).to_string();
+            world.despawn(pile);
+            world.gold[player_id] += amount;
+            self.statuses
+                .push(Status::new(format!("You pick up {}.", name), 1));
+        }
+    }
+
+    /// what the player has killed and how many of each, most killed first
+    pub fn kills_by_count(&self) -> Vec<(&str, u32)> {
+        let mut kills = self
+            .kills
+            .iter()
+            .map(|(monster, &count)| (monster.as_str(), count))
+            .collect::<Vec<_>>();
+        kills.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        kills
+    }
+
+    /// experience earned over the whole run, including what was spent on levels
+    pub fn total_xp(&self) -> i32 {
+        let world = self.world.borrow();
+        let experience = world.experience[world.player_id()];
+        let spent: i32 = (1..experience.level)
+            .map(|level| LEVEL_UP_BASE + level * LEVEL_UP_FACTOR)
+            .sum();
+        spent + experience.xp
+    }
+
+    pub fn score(&self) -> i32 {
+        let gold = {
+            let world = self.world.borrow();
+            world.gold.get(world.player_id()).copied().unwrap_or(0)
+        };
+        let kills: u32 = self.kills.values().sum();
+        gold + self.dungeon_level as i32 * SCORE_PER_DEPTH
+            + self.total_xp()
+            + kills as i32 * SCORE_PER_KILL
     }
 
     fn next_level(&mut self) {
-        self.graphics
-            .add_status("You take a moment to rest.".to_string(), 1);
-        let mut objs = self.graphics.objects.borrow_mut();
-        let heal_hp = objs[PLAYER].max_hp(&self.inventory) / 2;
-        objs[PLAYER].heal(heal_hp);
+        self.add_status("You take a moment to rest.".to_string(), 1);
+        let mut world = self.world.borrow_mut();
+        if let Some(notes) = self.levels.last_mut() {
+            notes.found = overview::survey(&self.map, &world);
+        }
+        // only the player and what they carry come along to the next level
+        world.clear_level();
+        let player_id = world.player_id();
+        let heal_hp = object::max_hp(&world, player_id) / 2;
+        object::heal(&mut world, player_id, heal_hp);
         self.dungeon_level += 1;
-        self.map = tile::make_map(&mut objs, self.dungeon_level);
+        self.map = tile::make_map(&mut world, self.dungeon_level);
+        self.levels
+            .push(LevelNotes::new(self.dungeon_level, self.turns));
     }
 
-    fn level_up(&mut self) {
-        let level_up_xp =
-            LEVEL_UP_BASE + self.graphics.objects.borrow()[PLAYER].level * LEVEL_UP_FACTOR;
-        // see if the player's experience is enough to level-up
-        if self.graphics.objects.borrow()[PLAYER].fighter.unwrap().xp >= level_up_xp {
-            // it is! level up
-            {
-                let player = &mut self.graphics.objects.borrow_mut()[PLAYER];
-
-                player.level += 1;
-                player.level_up_xp = level_up_xp;
-                self.graphics.statuses.push(Status::new(
-                    format!(
-                        "Your battle skills grow stronger! You reached level {}!",
-                        player.level
-                    ),
-                    1,
-                ));
-            }
+    /// whether the player has enough experience for the next level
+    fn can_level_up(&self) -> bool {
+        let world = self.world.borrow();
+        let experience = world.experience[world.player_id()];
+        experience.xp >= LEVEL_UP_BASE + experience.level * LEVEL_UP_FACTOR
+    }
 
-            let mut choice = None;
+    fn level_up(&mut self, stat: Stat) -> PlayerAction {
+        if !self.can_level_up() {
+            return PlayerAction::DidntTakeTurn;
+        }
 
-            while choice.is_none() {
-                // keep asking until a choice is made
-                self.graphics.statuses.push(Status::new(
-                    "Level up! Choose a stat to raise: (press the respective number)".to_string(),
-                    1,
-                ));
-                self.graphics
-                    .statuses
-                    .push(Status::new("0 - Constitution (+20 HP)".to_string(), 1));
-                self.graphics
-                    .statuses
-                    .push(Status::new("1 - Strength (+1 power)".to_string(), 1));
-                self.graphics
-                    .statuses
-                    .push(Status::new("2 - Agility (+1 defence)".to_string(), 1));
-
-                self.graphics.draw(&self.map);
-                self.graphics.draw_player_stats(
-                    &mut self.graphics.objects.borrow_mut()[PLAYER],
-                    self.dungeon_level,
-                    &self.inventory,
-                );
+        let mut world = self.world.borrow_mut();
+        let player_id = world.player_id();
+        let experience = &mut world.experience[player_id];
+        let level_up_xp = LEVEL_UP_BASE + experience.level * LEVEL_UP_FACTOR;
+        experience.xp -= level_up_xp;
+        experience.level += 1;
+        experience.level_up_xp = level_up_xp;
+        let level = experience.level;
 
-                let player = &mut self.graphics.objects.borrow_mut()[PLAYER];
-                let fighter = player.fighter.as_mut().unwrap();
-                choice = self.graphics.window.getch();
-                match choice {
-                    Some(Input::Character('0'..='2')) => {
-                        fighter.xp -= level_up_xp;
-                        match choice.unwrap() {
-                            Input::Character('0') => {
-                                fighter.base_max_hp += 20;
-                                fighter.hp += 20;
-                            }
-                            Input::Character('1') => {
-                                fighter.base_power += 1;
-                            }
-                            Input::Character('2') => {
-                                fighter.base_defence += 1;
-                            }
-                            _ => unreachable!(),
-                        }
-                    }
-                    _ => choice = None,
-                }
+        let fighter = &mut world.fighters[player_id];
+        match stat {
+            Stat::Constitution => {
+                fighter.base_max_hp += 20;
+                fighter.hp += 20;
+            }
+            Stat::Strength => {
+                fighter.base_power += 1;
+            }
+            Stat::Agility => {
+                fighter.base_defence += 1;
             }
         }
+        self.statuses.push(
+            Status::new(
+                format!(
+                    "Your battle skills grow stronger! You reached level {}!",
+                    level
+                ),
+                1,
+            )
+            .with_color(Color::Good),
+        );
+        PlayerAction::DidntTakeTurn
     }
 
     // ------------------------------------
     // inventory-related methods
-    fn show_inventory(&self) {
-        self.graphics.window.color_set(pancurses::COLOR_WHITE);
-        if !self.inventory.is_empty() {
-            self.graphics.window.mvaddstr(1, INV_X, "Inventory:");
-            for (i, item) in self.inventory.iter().enumerate() {
-                if item.equipment.is_some() && item.equipment.unwrap().equipped {
-                    self.graphics.window.mvaddstr(
-                        (i + 3) as i32,
-                        INV_X,
-                        format!(
-                            "{} - {} (on {})",
-                            (i + 97) as u8 as char,
-                            item.name.clone(),
-                            item.equipment.unwrap().slot
-                        ),
-                    );
-                } else {
-                    self.graphics.window.mvaddstr(
-                        (i + 3) as i32,
-                        INV_X,
-                        format!("{} - {}", (i + 97) as u8 as char, item.name.clone()),
-                    );
-                }
+    pub fn show_inventory(&self, graphics: &Graphics) {
+        let layout = graphics.layout();
+        let inv_x = match layout.inventory_x {
+            Some(inv_x) if !layout.is_too_small() => inv_x,
+            // the inventory is still listed when picking an item
+            _ => return,
+        };
+        let world = self.world.borrow();
+        let inventory = world.inventory(world.player_id());
+        if !inventory.is_empty() {
+            graphics.window.mvaddstr(1, inv_x, "Inventory:");
+            let rows = (layout.bottom() - 3).max(0) as usize;
+            let width = (layout.width - 1 - inv_x).max(0) as usize;
+            for (i, &item) in inventory.iter().enumerate().take(rows) {
+                let label = format!(
+                    "{} - {}",
+                    (i + 97) as u8 as char,
+                    describe_item(&world, item)
+                );
+                let label = label.chars().take(width).collect::<String>();
+                graphics.window.mvaddstr((i + 3) as i32, inv_x, label);
             }
         } else {
-            self.graphics
+            graphics
                 .window
-                .mvaddstr(1, INV_X, "Your inventory is empty.");
+                .mvaddstr(1, inv_x, "Your inventory is empty.");
         }
     }
 
-    fn apply_item(&mut self) -> PlayerAction {
-        self.graphics
-            .add_status("PRESS A KEY TO USE AN ITEM:".to_string(), 1);
-        self.graphics.draw(&self.map);
-        self.show_inventory();
-        self.graphics.draw_player_stats(
-            &mut self.graphics.objects.borrow_mut()[PLAYER],
-            self.dungeon_level,
-            &self.inventory,
+    fn inventory_len(&self) -> usize {
+        let world = self.world.borrow();
+        world.inventory(world.player_id()).len()
+    }
+
+    fn apply_item(
+        &mut self,
+        slot: usize,
+        direction: Option<(i32, i32)>,
+        target: Option<usize>,
+    ) -> PlayerAction {
+        if slot < self.inventory_len() {
+            return item::use_item(slot, direction, target, self);
+        }
+        self.add_status(
+            format!("You don't have an item at {}.", (slot + 97) as u8 as char),
+            1,
         );
-        match self.graphics.window.getch() {
-            Some(Input::Character(c)) => match c {
-                'a'..='z' => {
-                    let inv_id = c as u8 - 97;
-                    if (inv_id as usize) < self.inventory.len() {
-                        return item::use_item(inv_id as usize, self);
-                    } else {
-                        self.graphics
-                            .add_status(format!("You don't have an item at {}.", c), 1);
-                    }
-                }
-                _ => self
-                    .graphics
-                    .add_status("Please press a key from a to z.".to_string(), 1),
-            },
+        PlayerAction::DidntTakeTurn
+    }
 
-            Some(Input::KeyDC) => self.graphics.add_status("Cancelled.".to_string(), 1),
-            _ => self
-                .graphics
-                .add_status("Please press a key from a to z.".to_string(), 1),
+    fn drop_item(&mut self, slot: usize) -> PlayerAction {
+        if slot < self.inventory_len() {
+            let item = {
+                let world = self.world.borrow();
+                world.inventory(world.player_id())[slot]
+            };
+            let mut world = self.world.borrow_mut();
+            if world.equipment.contains(item) {
+                object::dequip(&mut world, item, &mut self.statuses);
+            }
+            if world.for_sale.contains(item) {
+                // unpaid items go back where the shopkeeper can see them
+                let player_id = world.player_id();
+                world.drop_item(player_id, slot);
+                let msg = format!(
+                    "You put the {} down. It is still for sale.",
+                    world.name(item)
+                );
+                self.statuses.push(Status::new(msg, 1));
+            } else {
+                world.despawn(item);
+            }
+        } else {
+            self.add_status(
+                format!("You don't have an item at {}.", (slot + 97) as u8 as char),
+                1,
+            );
         }
         PlayerAction::DidntTakeTurn
     }
+}
 
-    fn drop_item(&mut self) -> PlayerAction {
-        self.graphics
-            .add_status("PRESS A KEY TO DROP AN ITEM:".to_string(), 1);
-        self.graphics.draw(&self.map);
-        self.show_inventory();
-        self.graphics.draw_player_stats(
-            &mut self.graphics.objects.borrow_mut()[PLAYER],
-            self.dungeon_level,
-            &self.inventory,
-        );
-        match self.graphics.window.getch() {
-            Some(Input::Character(c)) => match c {
-                'a'..='z' => {
-                    let inv_id = (c as u8 - 97) as usize;
-                    if inv_id < self.inventory.len() {
-                        if self.inventory[inv_id].equipment.is_some() {
-                            self.inventory[inv_id].dequip(&mut self.graphics.statuses);
-                        }
-                        self.inventory.remove(inv_id);
-                    } else {
-                        self.graphics
-                            .add_status(format!("You don't have an item at {}.", c), 1);
-                    }
-                }
-                _ => self
-                    .graphics
-                    .add_status("Please press a key from a to z.".to_string(), 1),
-            },
+/// the player's character, as a new run starts them out
+fn spawn_player(world: &mut World, name: &str) -> ObjectId {
+    let player = world.spawn_at(0, 0, '@', Color::Player, true, name, true);
+    world.set_player(player);
+
+    world.vitals.insert(
+        player,
+        Vitals {
+            nutrition: PLAYER_DEF_NUTRITION,
+            poisoned: 0,
+        },
+    );
+
+    world.fighters.insert(
+        player,
+        Fighter {
+            base_max_hp: PLAYER_DEF_HP,
+            hp: PLAYER_DEF_HP,
+            base_defence: 1,
+            xp: 0,
+            base_power: 4,
+        },
+    );
+
+    world.experience.insert(
+        player,
+        Experience {
+            level: 1,
+            xp: 0,
+            level_up_xp: LEVEL_UP_BASE + LEVEL_UP_FACTOR,
+        },
+    );
+    world.gold.insert(player, 0);
+    world.inventories.insert(player, vec![]);
+
+    let dagger = world.spawn_at(0, 0, '-', Color::Weapon, false, "dagger", false);
+    world.items.insert(dagger, Item::Sword);
+    world.equipment.insert(
+        dagger,
+        Equipment {
+            equipped: true,
+            slot: Slot::LeftHand,
+            max_hp_bonus: 0,
+            defense_bonus: 0,
+            power_bonus: 2,
+        },
+    );
+    world.give(player, dagger);
+    player
+}
 
-            Some(Input::KeyDC) => self.graphics.add_status("Cancelled.".to_string(), 1),
-            _ => self
-                .graphics
-                .add_status("Please press a key from a to z.".to_string(), 1),
+/// whether exploring goes out of its way for an object lying around: items,
+/// unless they are for sale or remains, and gold if it is picked up by stepping on it
+fn is_collectable(world: &World, id: ObjectId, gold: bool) -> bool {
+    if world.for_sale.contains(id) || world.fighters.contains(id) {
+        return false;
+    }
+    match world.items.get(id) {
+        Some(item) => *item != Item::Corpse,
+        None => gold && world.gold.contains(id),
+    }
+}
+
+/// turns between each regenerated hit point, shorter for tougher and more experienced characters
+pub fn regen_interval(world: &World) -> u32 {
+    let player_id = world.player_id();
+    let constitution =
+        world.fighters.get(player_id).map_or(0, |f| f.base_max_hp) / REGEN_CON_FACTOR;
+    let bonus = (world.experience[player_id].level + constitution) as u32;
+    REGEN_BASE_INTERVAL.saturating_sub(bonus).max(1)
+}
+
+/// starving or poisoned bodies don't heal by themselves
+pub fn can_regenerate(world: &World) -> bool {
+    let vitals = world.vitals[world.player_id()];
+    vitals.poisoned == 0
+        && match vitals.hunger() {
+            Hunger::NotHungry | Hunger::Hungry => true,
+            Hunger::Weak | Hunger::Fainting | Hunger::Starved => false,
         }
-        PlayerAction::DidntTakeTurn
+}
+
+/// an item's name along with whatever the player needs to know about it
+pub fn describe_item(world: &World, item: ObjectId) -> String {
+    let mut label = world.name(item).to_string();
+    if let Some(equipment) = world.equipment.get(item).filter(|e| e.equipped) {
+        label.push_str(&format!(" (on {})", equipment.slot));
+    }
+    if let Some(charges) = world.charges.get(item) {
+        label.push_str(&format!(" ({} charges)", charges));
     }
+    if world.for_sale.contains(item) {
+        label.push_str(&format!(" (unpaid, {} gold)", shop::price(world, item)));
+    }
+    label
 }
 
 impl Default for Game {
     fn default() -> Self {
         Self {
-            map: vec![vec![Tile::empty(); MAP_HEIGHT as usize]; MAP_WIDTH as usize],
-            graphics: Graphics::default(),
-            inventory: vec![],
+            map: {
+                let (width, height) = tile::level_size(1);
+                vec![vec![Tile::empty(); height as usize]; width as usize]
+            },
+            world: RefCell::new(World::default()),
+            statuses: Vec::new(),
+            log: Vec::new(),
             dungeon_level: 1,
+            turns: 0,
+            kills: HashMap::new(),
+            seed: rng::random_seed(),
+            options: Options::load(),
+            keymap: Keymap::default(),
+            levels: vec![],
         }
     }
 }
@@ -451,5 +1215,228 @@ impl Default for Game {
 pub enum PlayerAction {
     TookTurn,
     DidntTakeTurn,
+    /// leave, saving the run
     Exit,
+    /// leave, throwing the run away
+    Abandon,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_gen::make_item;
+
+    /// a run on an open floor with nothing on it, the player in the middle
+    fn game() -> Game {
+        game_named("Tester")
+    }
+
+    fn game_named(name: &str) -> Game {
+        let game = Game {
+            map: vec![vec![Tile::empty(); 10]; 20],
+            ..Game::default()
+        };
+        let mut world = game.world.borrow_mut();
+        let player = spawn_player(&mut world, name);
+        world.set_pos(player, 10, 5);
+        drop(world);
+        game
+    }
+
+    /// a run on a level drawn with `#` for walls and `.` for floor, all of
+    /// it seen, and the player on the `@`
+    fn game_on(rows: &[&str]) -> Game {
+        let mut game = game();
+        let (width, height) = (rows[0].len(), rows.len());
+        game.map = vec![vec![Tile::empty(); height]; width];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                if ch == '#' {
+                    game.map[x][y] = Tile::wall();
+                }
+                game.map[x][y].visible = true;
+                if ch == '@' {
+                    let mut world = game.world.borrow_mut();
+                    let player_id = world.player_id();
+                    world.set_pos(player_id, x as i32, y as i32);
+                }
+            }
+        }
+        game
+    }
+
+    fn player_pos(game: &Game) -> (i32, i32) {
+        let world = game.world.borrow();
+        world.pos(world.player_id())
+    }
+
+    #[test]
+    fn moving_takes_one_step_and_a_turn() {
+        let mut game = game();
+        assert_eq!(game.execute(Command::Move(1, 0)), PlayerAction::TookTurn);
+        assert_eq!(player_pos(&game), (11, 5));
+        assert_eq!(game.execute(Command::Move(-5, 3)), PlayerAction::TookTurn);
+        assert_eq!(player_pos(&game), (10, 6));
+    }
+
+    #[test]
+    fn walls_stop_the_player() {
+        let mut game = game();
+        game.map[11][5] = Tile::wall();
+        game.execute(Command::Move(1, 0));
+        assert_eq!(player_pos(&game), (10, 5));
+    }
+
+    #[test]
+    fn picking_up_fills_the_inventory() {
+        let mut game = game();
+        let potion = make_item(&mut game.world.borrow_mut(), Item::Heal, 10, 5);
+        assert_eq!(game.execute(Command::Pickup), PlayerAction::TookTurn);
+        let world = game.world.borrow();
+        assert_eq!(world.inventory(world.player_id()).last(), Some(&potion));
+        assert!(world.positions.get(potion).is_none());
+    }
+
+    #[test]
+    fn levelling_up_needs_the_experience() {
+        let mut game = game();
+        game.execute(Command::LevelUp(Stat::Strength));
+        let power = {
+            let world = game.world.borrow();
+            world.fighters[world.player_id()].base_power
+        };
+        assert_eq!(power, 4);
+
+        {
+            let mut world = game.world.borrow_mut();
+            let player_id = world.player_id();
+            world.experience[player_id].xp = LEVEL_UP_BASE + LEVEL_UP_FACTOR;
+        }
+        game.execute(Command::LevelUp(Stat::Strength));
+        let world = game.world.borrow();
+        let player_id = world.player_id();
+        assert_eq!(world.fighters[player_id].base_power, 5);
+        assert_eq!(world.experience[player_id].level, 2);
+    }
+
+    #[test]
+    fn the_dead_do_not_regenerate() {
+        let mut game = game();
+        let player_id = game.world.borrow().player_id();
+        game.world.borrow_mut().fighters[player_id].hp = 0;
+        let interval = regen_interval(&game.world.borrow());
+        for _ in 0..=interval {
+            game.pass_time();
+        }
+        object::heal(&mut game.world.borrow_mut(), player_id, 10);
+        assert_eq!(game.world.borrow().fighters[player_id].hp, 0);
+        assert_eq!(game.turns, 0);
+    }
+
+    #[test]
+    fn the_dead_can_only_quit() {
+        let mut game = game();
+        {
+            let mut world = game.world.borrow_mut();
+            let player_id = world.player_id();
+            world.fighters[player_id].hp = 0;
+        }
+        assert_eq!(
+            game.execute(Command::Move(1, 0)),
+            PlayerAction::DidntTakeTurn
+        );
+        assert_eq!(player_pos(&game), (10, 5));
+        assert_eq!(game.execute(Command::Quit), PlayerAction::Exit);
+        assert_eq!(game.execute(Command::Abandon), PlayerAction::Abandon);
+    }
+
+    #[test]
+    fn stairs_are_found_by_marker_not_by_name() {
+        let mut game = game_named("stairs");
+        game.map[10][5].visible = true;
+        assert_eq!(game.known_stairs(), None);
+        game.execute(Command::Descend);
+        assert_eq!(game.dungeon_level, 1);
+
+        {
+            let mut world = game.world.borrow_mut();
+            let stairs = world.spawn_at(10, 5, '>', Color::Stairs, true, "ladder", false);
+            world.stairs.insert(stairs, ());
+        }
+        assert_eq!(game.known_stairs(), Some((10, 5)));
+    }
+
+    #[test]
+    fn unpaid_items_are_put_back_when_dropped() {
+        let mut game = game();
+        let (potion, shopkeeper) = {
+            let mut world = game.world.borrow_mut();
+            let shopkeeper = world.spawn_at(0, 0, '@', Color::Peaceful, true, "shopkeeper", true);
+            let potion = make_item(&mut world, Item::Heal, 10, 5);
+            world.for_sale.insert(potion, shopkeeper);
+            let player_id = world.player_id();
+            world.gold.insert(player_id, 100);
+            (potion, shopkeeper)
+        };
+        game.execute(Command::Pickup);
+        game.execute(Command::Drop(1));
+
+        let world = game.world.borrow();
+        let player_id = world.player_id();
+        assert_eq!(world.inventory(player_id).len(), 1);
+        assert_eq!(world.pos(potion), (10, 5));
+        assert_eq!(world.for_sale.get(potion), Some(&shopkeeper));
+        assert_eq!(world.gold.get(player_id), Some(&100));
+    }
+
+    #[test]
+    fn running_follows_a_corridor_to_its_end() {
+        let mut game = game_on(&[
+            "##########",
+            "#@....####",
+            "#####.####",
+            "#####....#",
+            "##########",
+        ]);
+        game.execute(Command::Run(1, 0));
+        assert_eq!(player_pos(&game), (8, 3));
+    }
+
+    #[test]
+    fn running_in_a_room_stops_at_the_wall() {
+        let mut game = game_on(&[
+            "############",
+            "#..........#",
+            "#@.........#",
+            "#..........#",
+            "############",
+        ]);
+        game.execute(Command::Run(1, 0));
+        assert_eq!(player_pos(&game), (10, 2));
+    }
+
+    #[test]
+    fn running_stops_in_doorways() {
+        let mut game = game_on(&[
+            "#########",
+            "#...#####",
+            "#@.......",
+            "#...#####",
+            "#########",
+        ]);
+        game.execute(Command::Run(1, 0));
+        assert_eq!(player_pos(&game), (4, 2));
+    }
+
+    #[test]
+    fn running_stops_on_things_lying_around() {
+        let mut game = game_on(&[
+            "####################",
+            "#@..................",
+            "####################",
+        ]);
+        make_item(&mut game.world.borrow_mut(), Item::Apple, 4, 1);
+        game.execute(Command::Run(1, 0));
+        assert_eq!(player_pos(&game), (4, 1));
+    }
 }
@@ -0,0 +1,246 @@
+use crate::paths;
+
+use std::fs;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SCORES_FILE: &str = "highscores";
+const LOCK_FILE: &str = "highscores.lock";
+const MAX_ENTRIES: usize = 100;
+pub const TOP_SHOWN: usize = 10;
+
+const LOCK_ATTEMPTS: u32 = 100;
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub score: i32,
+    pub name: String,
+    pub depth: u32,
+    pub level: i32,
+    pub cause: String,
+    pub date: String,
+}
+
+impl Entry {
+    pub fn new(score: i32, name: &str, depth: u32, level: i32, cause: &str) -> Self {
+        Self {
+            score,
+            name: name.into(),
+            depth,
+            level,
+            cause: cause.into(),
+            date: today(),
+        }
+    }
+
+    /// one tab-separated line of the scores file
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.score,
+            sanitize(&self.name),
+            self.depth,
+            self.level,
+            sanitize(&self.cause),
+            self.date
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() != 6 {
+            return None;
+        }
+        Some(Self {
+            score: fields[0].parse().ok()?,
+            name: fields[1].to_string(),
+            depth: fields[2].parse().ok()?,
+            level: fields[3].parse().ok()?,
+            cause: fields[4].to_string(),
+            date: fields[5].to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>7}  {} (level {}), {} on dungeon level {}, {}",
+            self.score, self.name, self.level, self.cause, self.depth, self.date
+        )
+    }
+}
+
+/// Held while the scores file is being rewritten, so two games can't clobber
+/// each other. It is an advisory lock on the lock file, which the OS lets go
+/// of when the game exits, crashed or not; the file itself stays.
+struct Lock(File);
+
+impl Lock {
+    fn acquire() -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(paths::data_dir().join(LOCK_FILE))?;
+        for _ in 0..LOCK_ATTEMPTS {
+            match file.try_lock() {
+                Ok(()) => return Ok(Lock(file)),
+                Err(TryLockError::WouldBlock) => thread::sleep(LOCK_RETRY),
+                Err(TryLockError::Error(e)) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "the high score file is locked by another game",
+        ))
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+/// all entries, best first
+pub fn load() -> Vec<Entry> {
+    let contents = fs::read_to_string(paths::data_dir().join(SCORES_FILE)).unwrap_or_default();
+    let mut entries = contents
+        .lines()
+        .filter_map(Entry::from_line)
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+    entries
+}
+
+/// add an entry to the table, returning its rank (starting at 0) if it made the cut
+pub fn record(entry: &Entry) -> io::Result<Option<usize>> {
+    fs::create_dir_all(paths::data_dir())?;
+    let _lock = Lock::acquire()?;
+
+    let mut entries = load();
+    let rank = entries
+        .iter()
+        .position(|other| other.score < entry.score)
+        .unwrap_or(entries.len());
+    entries.insert(rank, entry.clone());
+    entries.truncate(MAX_ENTRIES);
+
+    let contents = entries
+        .iter()
+        .map(|entry| entry.to_line() + "\n")
+        .collect::<String>();
+    paths::write_atomic(&paths::data_dir().join(SCORES_FILE), &contents)?;
+
+    Ok(if rank < MAX_ENTRIES { Some(rank) } else { None })
+}
+
+/// print the best entries to stdout, for the command-line flag
+pub fn print_top() {
+    let entries = load();
+    if entries.is_empty() {
+        println!("No high scores yet.");
+        return;
+    }
+    println!("Top scores:");
+    for (i, entry) in entries.iter().take(TOP_SHOWN).enumerate() {
+        println!("{:>2}. {}", i + 1, entry);
+    }
+}
+
+fn sanitize(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+/// the current date as YYYY-MM-DD, in UTC
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86400) as i64;
+    civil_date(days)
+}
+
+/// days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> Entry {
+        Entry {
+            score: 1234,
+            name: "Rodney".into(),
+            depth: 7,
+            level: 5,
+            cause: "killed by a troll".into(),
+            date: "2024-02-29".into(),
+        }
+    }
+
+    #[test]
+    fn line_round_trip() {
+        let entry = entry();
+        assert_eq!(Entry::from_line(&entry.to_line()), Some(entry));
+    }
+
+    #[test]
+    fn tabs_and_newlines_do_not_split_fields() {
+        let entry = Entry {
+            name: "Rod\tney".into(),
+            cause: "killed\nby a troll".into(),
+            ..entry()
+        };
+        let read = Entry::from_line(&entry.to_line()).unwrap();
+        assert_eq!(read.name, "Rod ney");
+        assert_eq!(read.cause, "killed by a troll");
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        assert_eq!(Entry::from_line(""), None);
+        assert_eq!(Entry::from_line("12\tRodney\t7"), None);
+        assert_eq!(
+            Entry::from_line("lots\tRodney\t7\t5\tkilled\t2024-02-29"),
+            None
+        );
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+        assert_eq!(civil_date(19_782), "2024-02-29");
+        assert_eq!(civil_date(19_783), "2024-03-01");
+        assert_eq!(civil_date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn today_is_a_date() {
+        let today = today();
+        let parts = today.split('-').collect::<Vec<_>>();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].len(), 4);
+        let month: u32 = parts[1].parse().unwrap();
+        let day: u32 = parts[2].parse().unwrap();
+        assert!((1..=12).contains(&month));
+        assert!((1..=31).contains(&day));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::character;
+use crate::command::{Command, Stat};
+use crate::curses::{Graphics, Menu};
+use crate::game::{self, Game};
+use crate::item::{self, Item};
+use crate::keymap::Action;
+use crate::overview;
+use crate::shop;
+use crate::store::ObjectId;
+use crate::tile;
+use crate::world::World;
+
+use pancurses::Input;
+
+/// wait for a key and turn it into a command; None for keys that don't mean
+/// anything and for prompts that were cancelled
+pub fn read_command(game: &mut Game, graphics: &mut Graphics) -> Option<Command> {
+    let key = graphics.window.getch()?;
+    if key == Input::KeyResize {
+        // the next redraw lays the screen out for the new size
+        pancurses::resize_term(0, 0);
+        return None;
+    }
+    match game.keymap.action(&key)? {
+        Action::Move(dx, dy) => match shopkeeper_at(game, dx, dy) {
+            Some(shopkeeper_id) => ask_trade(game, graphics, shopkeeper_id),
+            None => Some(Command::Move(dx, dy)),
+        },
+        Action::Run(dx, dy) => Some(Command::Run(dx, dy)),
+        Action::Travel => ask_destination(game, graphics).map(|(x, y)| Command::Travel(x, y)),
+        Action::Pickup => Some(Command::Pickup),
+        Action::Wait => Some(Command::Wait),
+        Action::Rest => Some(Command::Rest),
+        Action::Explore => ask_explore(game, graphics),
+        Action::Apply => ask_apply(game, graphics),
+        Action::Drop => {
+            ask_item(game, graphics, "Drop which item?", |_, _| true).map(Command::Drop)
+        }
+        Action::Descend => Some(Command::Descend),
+        Action::Overview => {
+            overview::show(game, graphics);
+            None
+        }
+        Action::Character => {
+            let width = graphics.layout().width.saturating_sub(4).max(0) as usize;
+            let lines = character::sheet(game, width);
+            graphics.show_text_screen("Character", &lines);
+            None
+        }
+        Action::Help => {
+            graphics.show_text_screen("Keys", &game.keymap.help());
+            None
+        }
+        Action::Quit => ask_quit(graphics),
+    }
+}
+
+/// quitting needs confirming, and whether the run is kept for later
+fn ask_quit(graphics: &Graphics) -> Option<Command> {
+    let menu = Menu::new("Quit the game?")
+        .keyed('s', "save and quit")
+        .keyed('a', "abandon run")
+        .keyed('c', "cancel");
+    match graphics.choose(&menu) {
+        Some(0) => Some(Command::Quit),
+        Some(1) => Some(Command::Abandon),
+        _ => None,
+    }
+}
+
+/// which item to use, and where to aim it if it needs aiming
+fn ask_apply(game: &mut Game, graphics: &mut Graphics) -> Option<Command> {
+    let slot = ask_item(game, graphics, "Use which item?", |_, _| true)?;
+    let (needs_direction, needs_wand) = {
+        let world = game.world.borrow();
+        let inventory = world.inventory(world.player_id());
+        let item = inventory[slot];
+        // without a wand, the scroll says there is nothing to recharge
+        let has_wand = inventory.iter().any(|&other| world.charges.contains(other));
+        let needs_wand = world.items.get(item) == Some(&Item::Recharging) && has_wand;
+        (item::needs_direction(&world, item), needs_wand)
+    };
+    let direction = if needs_direction {
+        Some(ask_direction(game, graphics)?)
+    } else {
+        None
+    };
+    let target = if needs_wand {
+        Some(ask_item(
+            game,
+            graphics,
+            "Recharge which wand?",
+            |world, item| world.charges.contains(item),
+        )?)
+    } else {
+        None
+    };
+    Some(Command::Apply {
+        slot,
+        direction,
+        target,
+    })
+}
+
+/// ask for a direction to zap a wand in
+fn ask_direction(game: &mut Game, graphics: &mut Graphics) -> Option<(i32, i32)> {
+    const DIRECTIONS: [(i32, i32); 8] = [
+        (0, -1),
+        (0, 1),
+        (-1, 0),
+        (1, 0),
+        (-1, -1),
+        (1, -1),
+        (-1, 1),
+        (1, 1),
+    ];
+    game.draw(graphics);
+    // the movement keys, arrows included
+    let menu = DIRECTIONS
+        .iter()
+        .fold(Menu::new("In what direction?"), |menu, &(dx, dy)| {
+            menu.keyed_by(game.keymap.keys(Action::Move(dx, dy)), "")
+        })
+        .prompt();
+    let picked = graphics.choose(&menu).map(|index| DIRECTIONS[index]);
+    if picked.is_none() {
+        game.add_status("Cancelled.".to_string(), 1);
+    }
+    picked
+}
+
+/// a peaceful shopkeeper the player would walk into, when the player is in
+/// their shop
+fn shopkeeper_at(game: &Game, dx: i32, dy: i32) -> Option<ObjectId> {
+    let world = game.world.borrow();
+    let shopkeeper_id = shop::shopkeeper_serving(&world)?;
+    let (x, y) = world.pos(world.player_id());
+    if world.pos(shopkeeper_id) == (x + dx, y + dy) {
+        Some(shopkeeper_id)
+    } else {
+        None
+    }
+}
+
+/// the buy and sell dialog, opened by walking into a peaceful shopkeeper
+fn ask_trade(game: &mut Game, graphics: &mut Graphics, shopkeeper_id: ObjectId) -> Option<Command> {
+    let menu = Menu::new("\"Welcome! What can I do for you?\"")
+        .keyed('b', "buy")
+        .keyed('s', "sell");
+    match graphics.choose(&menu) {
+        Some(0) => ask_buy(game, graphics, shopkeeper_id),
+        Some(1) => ask_sell(game, graphics),
+        _ => {
+            game.add_status("Cancelled.".to_string(), 1);
+            None
+        }
+    }
+}
+
+fn ask_buy(game: &mut Game, graphics: &Graphics, shopkeeper_id: ObjectId) -> Option<Command> {
+    let sold_here =
+        |world: &World, item: ObjectId| world.for_sale.get(item) == Some(&shopkeeper_id);
+    let unpaid = {
+        let world = game.world.borrow();
+        let inventory = world.inventory(world.player_id());
+        inventory.iter().any(|item| sold_here(&world, *item))
+    };
+    if !unpaid {
+        game.add_status("\"Pick up what you'd like to buy first.\"".to_string(), 1);
+        return None;
+    }
+    ask_item(game, graphics, "Buy which item?", sold_here).map(Command::Buy)
+}
+
+fn ask_sell(game: &mut Game, graphics: &Graphics) -> Option<Command> {
+    let slot = ask_item(game, graphics, "Sell which item?", |world, item| {
+        !world.for_sale.contains(item)
+    })?;
+    let question = {
+        let world = game.world.borrow();
+        let item = world.inventory(world.player_id())[slot];
+        format!(
+            "\"I'll give you {} gold for your {}.\" Sell it?",
+            shop::offer(&world, item),
+            world.name(item)
+        )
+    };
+    if !graphics.ask_yes_no(&question) {
+        game.add_status("Cancelled.".to_string(), 1);
+        return None;
+    }
+    Some(Command::Sell(slot))
+}
+
+/// explore, or once the level is explored, offer to walk to the stairs
+fn ask_explore(game: &mut Game, graphics: &Graphics) -> Option<Command> {
+    let player = {
+        let world = game.world.borrow();
+        world.pos(world.player_id())
+    };
+    match game.known_stairs() {
+        Some((x, y)) if (x, y) != player && game.is_explored() => {
+            if graphics.ask_yes_no("Nothing is left to explore. Travel to the stairs?") {
+                Some(Command::Travel(x, y))
+            } else {
+                None
+            }
+        }
+        _ => Some(Command::Explore),
+    }
+}
+
+/// Pick a place on the map with a cursor, moved by the movement keys and
+/// eight at a time by the running keys. It starts on the stairs if they have
+/// been seen; the travel key or `>` go back to them.
+fn ask_destination(game: &mut Game, graphics: &mut Graphics) -> Option<(i32, i32)> {
+    let destination = move_cursor(game, graphics);
+    // the view goes back to the player
+    graphics.look_at = None;
+    destination
+}
+
+/// the cursor of `ask_destination`, with the view scrolling along with it
+fn move_cursor(game: &mut Game, graphics: &mut Graphics) -> Option<(i32, i32)> {
+    let player = {
+        let world = game.world.borrow();
+        world.pos(world.player_id())
+    };
+    let stairs = game.known_stairs();
+    let (mut x, mut y) = stairs.unwrap_or(player);
+
+    loop {
+        graphics.look_at = Some((x, y));
+        game.draw(graphics);
+        graphics.draw_cursor(
+            x,
+            y,
+            "Travel where? Move the cursor, Enter or . to go, > for the stairs.",
+        );
+
+        let key = graphics.window.getch()?;
+        let (dx, dy) = match (key, game.keymap.action(&key)) {
+            (Input::Character('\n'), _) | (Input::KeyEnter, _) | (Input::Character('.'), _) => {
+                return Some((x, y))
+            }
+            (Input::Character('\u{1b}'), _) => {
+                game.add_status("Cancelled.".to_string(), 1);
+                return None;
+            }
+            (Input::KeyResize, _) => {
+                pancurses::resize_term(0, 0);
+                continue;
+            }
+            (Input::Character('>'), _) | (_, Some(Action::Travel)) => {
+                if let Some(stairs) = stairs {
+                    (x, y) = stairs;
+                }
+                continue;
+            }
+            (_, Some(Action::Move(dx, dy))) => (dx, dy),
+            (_, Some(Action::Run(dx, dy))) => (dx * 8, dy * 8),
+            _ => continue,
+        };
+        // the cursor stays on the map
+        for _ in 0..dx.abs().max(dy.abs()) {
+            let (next_x, next_y) = (x + dx.signum(), y + dy.signum());
+            if !tile::in_bounds(next_x, next_y, &game.map) {
+                break;
+            }
+            (x, y) = (next_x, next_y);
+        }
+    }
+}
+
+/// ask for one of the inventory items `wanted` accepts, returning its
+/// position; the items keep their inventory letters
+pub fn ask_item(
+    game: &mut Game,
+    graphics: &Graphics,
+    title: &str,
+    wanted: impl Fn(&World, ObjectId) -> bool,
+) -> Option<usize> {
+    let (menu, listed, carried) = {
+        let world = game.world.borrow();
+        let inventory = world.inventory(world.player_id());
+        let mut menu = Menu::new(title);
+        let mut listed = 0;
+        for (i, &item) in inventory.iter().enumerate() {
+            if wanted(&world, item) {
+                menu = menu.keyed((i + 97) as u8 as char, &game::describe_item(&world, item));
+                listed += 1;
+            }
+        }
+        (menu, listed, inventory.len())
+    };
+    if listed == 0 {
+        let msg = if carried == 0 {
+            "Your inventory is empty."
+        } else {
+            "You don't have anything for that."
+        };
+        game.add_status(msg.to_string(), 1);
+        return None;
+    }
+
+    let slot = graphics.choose(&menu).map(|index| {
+        let world = game.world.borrow();
+        let inventory = world.inventory(world.player_id());
+        (0..inventory.len())
+            .filter(|&i| wanted(&world, inventory[i]))
+            .nth(index)
+            .expect("the menu lists wanted items only")
+    });
+    if slot.is_none() {
+        game.add_status("Cancelled.".to_string(), 1);
+    }
+    slot
+}
+
+/// keep asking until one of the stats is picked
+pub fn choose_stat(graphics: &Graphics) -> Stat {
+    let menu = Menu::new("Level up! Choose a stat to raise:")
+        .keyed('0', "Constitution (+20 HP)")
+        .keyed('1', "Strength (+1 power)")
+        .keyed('2', "Agility (+1 defence)");
+    loop {
+        match graphics.choose(&menu) {
+            Some(0) => return Stat::Constitution,
+            Some(1) => return Stat::Strength,
+            Some(2) => return Stat::Agility,
+            _ => {}
+        }
+    }
+}
@@ -1,7 +1,16 @@
-use crate::curses::{Status, PLAYER};
+use crate::curses::Status;
 use crate::game::{Game, PlayerAction};
+use crate::map_gen;
 use crate::object;
-use crate::object::Object;
+use crate::object::{DamageSource, Hunger};
+use crate::rng;
+use crate::shop;
+use crate::store::ObjectId;
+use crate::theme::Color;
+use crate::tile::{self, is_blocked};
+use crate::world::World;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 const HEAL_AMOUNT: i32 = 5;
 
@@ -15,7 +24,21 @@ const FIRE_RADIUS: i32 = 3;
 const FIRE_DAMAGE: i32 = 12;
 const FIRE_SELF_DAMAGE: i32 = 3;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+const WAND_RANGE: i32 = 8;
+const STRIKING_DAMAGE: i32 = 15;
+const DIGGING_RANGE: i32 = 12;
+const SLEEP_NUM_TURNS: i32 = 15;
+const RECHARGE_MIN: i32 = 3;
+const RECHARGE_MAX: i32 = 7;
+
+const MAX_NUTRITION: i32 = 2000;
+const RATION_NUTRITION: i32 = 800;
+const APPLE_NUTRITION: i32 = 50;
+const CORPSE_NUTRITION_FACTOR: i32 = 20;
+const CORPSE_POISON_CHANCE: u32 = 4; // one in four
+const CORPSE_POISON_TURNS: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Item {
     Heal,
     Lightning,
@@ -23,9 +46,17 @@ pub enum Item {
     Fire,
     Sword,
     Shield,
+    Striking,
+    Digging,
+    Sleep,
+    TeleportOther,
+    Recharging,
+    Ration,
+    Apple,
+    Corpse,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 /// An object that can be equipped, yielding bonuses.
 pub struct Equipment {
     pub slot: Slot,
@@ -35,7 +66,7 @@ pub struct Equipment {
     pub defense_bonus: i32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Slot {
     LeftHand,
     RightHand,
@@ -45,97 +76,137 @@ pub enum Slot {
 enum UseResult {
     UsedUp,
     UsedAndKept,
+    Zapped,
+    NothingHappens,
     Cancelled,
 }
 
-pub fn use_item(inv_id: usize, game: &mut Game) -> PlayerAction {
-    if let Some(item) = game.inventory[inv_id].item {
-        let on_use = match item {
-            Item::Heal => cast_heal,
-            Item::Lightning => cast_lightning,
-            Item::Confusion => cast_confusion,
-            Item::Fire => cast_fire,
-            Item::Sword => toggle_equipment,
-            Item::Shield => toggle_equipment,
+/// Use the item at a position of the inventory. Wands are zapped in
+/// `direction`, scrolls of recharging recharge the wand at `target`; without
+/// those they are cancelled.
+pub fn use_item(
+    inv_id: usize,
+    direction: Option<(i32, i32)>,
+    target: Option<usize>,
+    game: &mut Game,
+) -> PlayerAction {
+    let (item_id, item, charges) = {
+        let world = game.world.borrow();
+        let item_id = world.inventory(world.player_id())[inv_id];
+        let charges = world.charges.get(item_id).copied();
+        (item_id, world.items.get(item_id).copied(), charges)
+    };
+    // a single step's worth of direction, and none at all for standing still
+    let direction = direction
+        .map(|(dx, dy)| (dx.signum(), dy.signum()))
+        .filter(|&direction| direction != (0, 0));
+    if let Some(item) = item {
+        let result = match item {
+            // a wand with no charges left does nothing at all
+            _ if charges == Some(0) => UseResult::NothingHappens,
+            Item::Heal => cast_heal(item_id, game),
+            Item::Lightning => cast_lightning(item_id, game),
+            Item::Confusion => cast_confusion(item_id, game),
+            Item::Fire => cast_fire(item_id, game),
+            Item::Sword => toggle_equipment(item_id, game),
+            Item::Shield => toggle_equipment(item_id, game),
+            Item::Striking => zap_striking(direction, game),
+            Item::Digging => zap_digging(direction, game),
+            Item::Sleep => zap_sleep(direction, game),
+            Item::TeleportOther => zap_teleport_other(direction, game),
+            Item::Recharging => cast_recharging(item_id, target, game),
+            Item::Ration => eat(item_id, game),
+            Item::Apple => eat(item_id, game),
+            Item::Corpse => eat(item_id, game),
         };
-        match on_use(inv_id, game) {
+        match result {
             UseResult::UsedUp => {
-                game.inventory.remove(inv_id);
+                shop::charge_for(item_id, game);
+                game.world.borrow_mut().despawn(item_id);
             }
             UseResult::UsedAndKept => (),
-            UseResult::Cancelled => game
-                .graphics
-                .add_status("Cancelled item use.".to_string(), 1),
+            UseResult::Zapped => {
+                shop::charge_for(item_id, game);
+                if let Some(charges) = game.world.borrow_mut().charges.get_mut(item_id) {
+                    *charges -= 1;
+                }
+            }
+            UseResult::NothingHappens => game.add_status("Nothing happens.".to_string(), 1),
+            UseResult::Cancelled => game.add_status("Cancelled item use.".to_string(), 1),
         }
         PlayerAction::TookTurn
     } else {
-        game.graphics.add_status(
-            format!("The {} cannot be used.", game.inventory[inv_id].name),
-            1,
-        );
+        let name = game.world.borrow().name(item_id).to_string();
+        game.add_status(format!("The {} cannot be used.", name), 1);
         PlayerAction::DidntTakeTurn
     }
 }
-fn cast_heal(_inv_id: usize, game: &mut Game) -> UseResult {
-    let player = &mut game.graphics.objects.borrow_mut()[PLAYER];
-    if let Some(fighter) = player.fighter.as_mut() {
-        if fighter.hp == player.max_hp(&game.inventory) {
-            game.graphics.statuses.push(Status::new(
+fn cast_heal(_item_id: ObjectId, game: &mut Game) -> UseResult {
+    let mut world = game.world.borrow_mut();
+    let player_id = world.player_id();
+    if let Some(fighter) = world.fighters.get(player_id) {
+        if fighter.hp == object::max_hp(&world, player_id) {
+            game.statuses.push(Status::new(
                 "You are already at full health.".to_string(),
                 1,
             ));
             return UseResult::Cancelled;
         }
-        game.graphics.statuses.push(Status::new(
+        game.statuses.push(Status::new(
             "Your wounds start to feel better!".to_string(),
             1,
         ));
-        player.heal(HEAL_AMOUNT);
+        object::heal(&mut world, player_id, HEAL_AMOUNT);
         return UseResult::UsedUp;
     }
     UseResult::Cancelled
 }
 
-fn toggle_equipment(inv_id: usize, game: &mut Game) -> UseResult {
-    let equipment = match game.inventory[inv_id].equipment {
-        Some(equipment) => equipment,
+fn toggle_equipment(item_id: ObjectId, game: &mut Game) -> UseResult {
+    let mut world = game.world.borrow_mut();
+    let equipment = match world.equipment.get(item_id) {
+        Some(equipment) => *equipment,
         None => return UseResult::Cancelled,
     };
     if equipment.equipped {
-        game.inventory[inv_id].dequip(&mut game.graphics.statuses);
+        object::dequip(&mut world, item_id, &mut game.statuses);
     } else {
-        game.inventory[inv_id].equip(&mut game.graphics.statuses);
+        object::equip(&mut world, item_id, &mut game.statuses);
     }
 
-    if let Some(current) = object::get_equipped_in_slot(equipment.slot, &game.inventory) {
-        game.inventory[current].dequip(&mut game.graphics.statuses);
+    let player_id = world.player_id();
+    if let Some(current) = object::get_equipped_in_slot(&world, player_id, equipment.slot) {
+        object::dequip(&mut world, current, &mut game.statuses);
     }
 
     UseResult::UsedAndKept
 }
 
-fn cast_lightning(_inv_id: usize, game: &mut Game) -> UseResult {
-    let monster_id = closest_monster(&game.graphics.objects.borrow().clone(), LIGHTNING_RANGE);
-    if let Some(monster_id) = monster_id {
-        game.graphics.add_status(
+fn cast_lightning(_item_id: ObjectId, game: &mut Game) -> UseResult {
+    let mut world = game.world.borrow_mut();
+    if let Some(monster_id) = closest_monster(&world, LIGHTNING_RANGE) {
+        let name = world.name(monster_id).to_string();
+        game.statuses.push(Status::new(
             format!(
                 "Zapt! A thunder strikes {} doing {} damage!",
-                game.graphics.objects.borrow()[monster_id].name,
-                LIGHTNING_DAMAGE
+                name, LIGHTNING_DAMAGE
             ),
             1,
-        );
+        ));
 
-        let objs = &mut game.graphics.objects.borrow_mut();
+        let source = DamageSource::Item("scroll of lightning".to_string());
+        let statuses = &mut game.statuses;
         if let Some(xp) =
-            objs[monster_id].take_damage(LIGHTNING_DAMAGE, &mut game.graphics.statuses)
+            object::take_damage(&mut world, monster_id, LIGHTNING_DAMAGE, source, statuses)
         {
-            objs[PLAYER].fighter.as_mut().unwrap().xp += xp;
+            let player_id = world.player_id();
+            object::gain_xp(&mut world, player_id, xp);
+            *game.kills.entry(name).or_insert(0) += 1;
         };
         UseResult::UsedUp
     } else {
-        game.graphics
-            .add_status("No enemy is close enough.".to_string(), 1);
+        game.statuses
+            .push(Status::new("No enemy is close enough.".to_string(), 1));
         UseResult::Cancelled
     }
 }
@@ -150,35 +221,34 @@ impl std::fmt::Display for Slot {
     }
 }
 
-fn cast_confusion(_inv_id: usize, game: &mut Game) -> UseResult {
+fn cast_confusion(_item_id: ObjectId, game: &mut Game) -> UseResult {
     use crate::ai::AI;
 
-    let monster_id = closest_monster(&game.graphics.objects.borrow(), CONFUSION_RANGE);
-    if let Some(monster_id) = monster_id {
-        game.graphics.add_status(
-            format!(
-                "You confused {}!",
-                game.graphics.objects.borrow()[monster_id].name,
-            ),
+    let mut world = game.world.borrow_mut();
+    if let Some(monster_id) = closest_monster(&world, CONFUSION_RANGE) {
+        game.statuses.push(Status::new(
+            format!("You confused {}!", world.name(monster_id)),
             1,
+        ));
+        let old_ai = world.ais.remove(monster_id).unwrap_or(AI::Basic);
+        world.ais.insert(
+            monster_id,
+            AI::Confused {
+                prev_ai: Box::new(old_ai),
+                turns: CONFUSION_NUM_TURNS,
+            },
         );
-        let mut objs = game.graphics.objects.borrow_mut();
-        let old_ai = objs[monster_id].ai.take().unwrap_or(AI::Basic);
-        objs[monster_id].ai = Some(AI::Confused {
-            prev_ai: Box::new(old_ai),
-            turns: CONFUSION_NUM_TURNS,
-        });
 
         UseResult::UsedUp
     } else {
-        game.graphics
-            .add_status("No enemy is close enough.".to_string(), 1);
+        game.statuses
+            .push(Status::new("No enemy is close enough.".to_string(), 1));
         UseResult::Cancelled
     }
 }
 
-fn cast_fire(_inv_id: usize, game: &mut Game) -> UseResult {
-    game.graphics.add_status(
+fn cast_fire(_item_id: ObjectId, game: &mut Game) -> UseResult {
+    game.add_status(
         format!(
             "A wall of fire is created in the {} tiles around you!",
             FIRE_RADIUS
@@ -186,47 +256,57 @@ fn cast_fire(_inv_id: usize, game: &mut Game) -> UseResult {
         1,
     );
 
-    let player = game.graphics.objects.borrow()[PLAYER].clone();
+    let mut world = game.world.borrow_mut();
+    let player_id = world.player_id();
     let mut xp_to_gain = 0;
 
-    let mut objs = game.graphics.objects.borrow_mut();
-    for obj in objs.iter_mut() {
-        if obj.distance_to(&player) <= FIRE_RADIUS as f32 && obj.fighter.is_some() {
-            if obj.name == "player" {
-                game.graphics.statuses.push(Status::new(
-                    format!("You caught fire for {} hp.", FIRE_SELF_DAMAGE),
-                    1,
-                ));
-                obj.take_damage(FIRE_SELF_DAMAGE, &mut game.graphics.statuses);
-            } else {
-                game.graphics.statuses.push(Status::new(
-                    format!(
-                        "The {} gets burned for {} hit points.",
-                        obj.name, FIRE_DAMAGE
-                    ),
-                    1,
-                ));
-                if let Some(xp) = obj.take_damage(FIRE_DAMAGE, &mut game.graphics.statuses) {
-                    xp_to_gain += xp;
-                }
+    // only what stands on the map and can fight gets burned
+    for id in world.fighters.ids() {
+        if !world.positions.contains(id)
+            || world.distance(id, player_id) > FIRE_RADIUS as f32
+            || !world.is_alive(id)
+        {
+            continue;
+        }
+        let source = DamageSource::Item("scroll of fire".to_string());
+        if id == player_id {
+            game.statuses.push(Status::new(
+                format!("You caught fire for {} hp.", FIRE_SELF_DAMAGE),
+                1,
+            ));
+            object::take_damage(&mut world, id, FIRE_SELF_DAMAGE, source, &mut game.statuses);
+        } else {
+            let name = world.name(id).to_string();
+            game.statuses.push(Status::new(
+                format!("The {} gets burned for {} hit points.", name, FIRE_DAMAGE),
+                1,
+            ));
+            let statuses = &mut game.statuses;
+            if let Some(xp) = object::take_damage(&mut world, id, FIRE_DAMAGE, source, statuses) {
+                xp_to_gain += xp;
+                *game.kills.entry(name).or_insert(0) += 1;
             }
         }
     }
 
-    objs[PLAYER].fighter.as_mut().unwrap().xp += xp_to_gain;
+    object::gain_xp(&mut world, player_id, xp_to_gain);
 
     UseResult::UsedUp
 }
 
 /// find closest enemy, up to a maximum range
-fn closest_monster(objects: &[Object], max_range: i32) -> Option<usize> {
+fn closest_monster(world: &World, max_range: i32) -> Option<ObjectId> {
+    use crate::ai::AI;
+
     let mut closest_enemy = None;
     let mut closest_dist = (max_range + 1) as f32; // start with (slightly more than) maximum range
 
-    for (id, object) in objects.iter().enumerate() {
-        if (id != PLAYER) && object.fighter.is_some() && object.ai.is_some() {
+    let player_id = world.player_id();
+    for (id, ai) in world.ais.iter() {
+        // peaceful shopkeepers aren't enemies
+        if id != player_id && world.fighters.contains(id) && !matches!(ai, AI::Shopkeeper { .. }) {
             // calculate distance between this object and the player
-            let dist = objects[PLAYER].distance_to(object);
+            let dist = world.distance(player_id, id);
             if dist < closest_dist {
                 // it's closer, so remember it
                 closest_enemy = Some(id);
@@ -236,3 +316,309 @@ fn closest_monster(objects: &[Object], max_range: i32) -> Option<usize> {
     }
     closest_enemy
 }
+
+/// whether using an item means zapping it in a direction
+pub fn needs_direction(world: &World, item: ObjectId) -> bool {
+    let zapped = matches!(
+        world.items.get(item),
+        Some(Item::Striking | Item::Digging | Item::Sleep | Item::TeleportOther)
+    );
+    zapped && world.charges.get(item) != Some(&0)
+}
+
+/// follow a line from the player until a wall, returning the first monster on it
+fn monster_in_direction(game: &Game, dx: i32, dy: i32, max_range: i32) -> Option<ObjectId> {
+    use crate::ai::AI;
+
+    let world = game.world.borrow();
+    let (mut x, mut y) = world.pos(world.player_id());
+    for _ in 0..max_range {
+        x += dx;
+        y += dy;
+        if game.map[x as usize][y as usize].blocked {
+            return None;
+        }
+        // peaceful shopkeepers aren't enemies, bolts go past them
+        let monster = world.at(x, y).find(|&id| {
+            world.is_alive(id) && !matches!(world.ais.get(id), Some(AI::Shopkeeper { .. }))
+        });
+        if let Some(monster_id) = monster {
+            return Some(monster_id);
+        }
+    }
+    None
+}
+
+fn zap_striking(direction: Option<(i32, i32)>, game: &mut Game) -> UseResult {
+    let (dx, dy) = match direction {
+        Some(direction) => direction,
+        None => return UseResult::Cancelled,
+    };
+
+    if let Some(monster_id) = monster_in_direction(game, dx, dy, WAND_RANGE) {
+        let mut world = game.world.borrow_mut();
+        let name = world.name(monster_id).to_string();
+        game.statuses.push(Status::new(
+            format!(
+                "A force bolt hits {} for {} hit points!",
+                name, STRIKING_DAMAGE
+            ),
+            1,
+        ));
+
+        let source = DamageSource::Item("wand of striking".to_string());
+        let statuses = &mut game.statuses;
+        if let Some(xp) =
+            object::take_damage(&mut world, monster_id, STRIKING_DAMAGE, source, statuses)
+        {
+            let player_id = world.player_id();
+            object::gain_xp(&mut world, player_id, xp);
+            *game.kills.entry(name).or_insert(0) += 1;
+        };
+    } else {
+        game.add_status("The bolt hits nothing.".to_string(), 1);
+    }
+    UseResult::Zapped
+}
+
+fn zap_digging(direction: Option<(i32, i32)>, game: &mut Game) -> UseResult {
+    let (dx, dy) = match direction {
+        Some(direction) => direction,
+        None => return UseResult::Cancelled,
+    };
+
+    let (x, y) = {
+        let world = game.world.borrow();
+        world.pos(world.player_id())
+    };
+    map_gen::dig(x, y, dx, dy, DIGGING_RANGE, &mut game.map);
+    game.add_status("You dig a tunnel through the rock.".to_string(), 1);
+    UseResult::Zapped
+}
+
+fn zap_sleep(direction: Option<(i32, i32)>, game: &mut Game) -> UseResult {
+    use crate::ai::AI;
+
+    let (dx, dy) = match direction {
+        Some(direction) => direction,
+        None => return UseResult::Cancelled,
+    };
+
+    if let Some(monster_id) = monster_in_direction(game, dx, dy, WAND_RANGE) {
+        let mut world = game.world.borrow_mut();
+        game.statuses.push(Status::new(
+            format!("The {} falls asleep!", world.name(monster_id)),
+            1,
+        ));
+        let old_ai = world.ais.remove(monster_id).unwrap_or(AI::Basic);
+        world.ais.insert(
+            monster_id,
+            AI::Asleep {
+                prev_ai: Box::new(old_ai),
+                turns: SLEEP_NUM_TURNS,
+            },
+        );
+    } else {
+        game.add_status("The bolt hits nothing.".to_string(), 1);
+    }
+    UseResult::Zapped
+}
+
+fn zap_teleport_other(direction: Option<(i32, i32)>, game: &mut Game) -> UseResult {
+    let (dx, dy) = match direction {
+        Some(direction) => direction,
+        None => return UseResult::Cancelled,
+    };
+
+    if let Some(monster_id) = monster_in_direction(game, dx, dy, WAND_RANGE) {
+        let mut world = game.world.borrow_mut();
+        // keep trying random spots until a free one is found
+        loop {
+            let x = rng::get().gen_range(1, tile::width(&game.map) - 1);
+            let y = rng::get().gen_range(1, tile::height(&game.map) - 1);
+            if !is_blocked(x, y, &game.map, &world) {
+                game.statuses.push(Status::new(
+                    format!("The {} vanishes!", world.name(monster_id)),
+                    1,
+                ));
+                world.set_pos(monster_id, x, y);
+                break;
+            }
+        }
+    } else {
+        game.add_status("The bolt hits nothing.".to_string(), 1);
+    }
+    UseResult::Zapped
+}
+
+fn cast_recharging(item_id: ObjectId, target: Option<usize>, game: &mut Game) -> UseResult {
+    let has_wand = {
+        let world = game.world.borrow();
+        let inventory = world.inventory(world.player_id());
+        inventory.iter().any(|item| world.charges.contains(*item))
+    };
+    if !has_wand {
+        game.add_status("You have nothing to recharge.".to_string(), 1);
+        return UseResult::Cancelled;
+    }
+
+    let wand_index = match target {
+        Some(wand_index) => wand_index,
+        None => return UseResult::Cancelled,
+    };
+
+    let mut world = game.world.borrow_mut();
+    let player_id = world.player_id();
+    let wand_id = match world.inventory(player_id).get(wand_index) {
+        Some(&wand_id) if wand_id != item_id => wand_id,
+        _ => return UseResult::Cancelled,
+    };
+
+    let name = world.name(wand_id).to_string();
+    if let Some(charges) = world.charges.get_mut(wand_id) {
+        *charges += rng::get().gen_range(RECHARGE_MIN, RECHARGE_MAX + 1);
+        game.statuses.push(Status::new(
+            format!("Your {} glows blue for a moment.", name),
+            1,
+        ));
+        UseResult::UsedUp
+    } else {
+        game.statuses
+            .push(Status::new(format!("The {} is not a wand.", name), 1));
+        UseResult::Cancelled
+    }
+}
+
+fn eat(item_id: ObjectId, game: &mut Game) -> UseResult {
+    let mut world = game.world.borrow_mut();
+    let food = world.items.get(item_id).copied();
+    let nutrition = match food {
+        Some(Item::Ration) => RATION_NUTRITION,
+        Some(Item::Apple) => APPLE_NUTRITION,
+        // bigger monsters make for bigger meals
+        Some(Item::Corpse) => world
+            .fighters
+            .get(item_id)
+            .map_or(APPLE_NUTRITION, |f| f.base_max_hp * CORPSE_NUTRITION_FACTOR),
+        _ => return UseResult::Cancelled,
+    };
+    let tainted = food == Some(Item::Corpse) && rng::get().gen_range(0, CORPSE_POISON_CHANCE) == 0;
+
+    game.statuses.push(Status::new(
+        format!("You eat the {}.", world.name(item_id)),
+        1,
+    ));
+
+    let player_id = world.player_id();
+    let vitals = &mut world.vitals[player_id];
+    if vitals.nutrition + nutrition > MAX_NUTRITION {
+        game.statuses.push(Status::new(
+            "You're having a hard time getting all of it down.".to_string(),
+            1,
+        ));
+    }
+    let before = vitals.hunger();
+    vitals.nutrition = std::cmp::min(vitals.nutrition + nutrition, MAX_NUTRITION);
+    let after = vitals.hunger();
+    if before != after {
+        let msg = match after {
+            Hunger::NotHungry => "You are no longer hungry.",
+            _ => "You feel less hungry.",
+        };
+        game.statuses
+            .push(Status::new(msg.to_string(), 1).with_color(Color::Good));
+    }
+
+    if tainted {
+        game.statuses.push(Status::new(
+            "Ulch - that meat was tainted! You feel very sick.".to_string(),
+            1,
+        ));
+        vitals.poisoned += CORPSE_POISON_TURNS;
+    }
+    UseResult::UsedUp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AI;
+    use crate::map_gen::Rect;
+    use crate::object::{Fighter, Vitals};
+    use crate::tile::Tile;
+
+    fn fighter(world: &mut World, x: i32, name: &str) -> ObjectId {
+        let id = world.spawn_at(x, 5, '@', Color::Player, true, name, true);
+        world.fighters.insert(
+            id,
+            Fighter {
+                base_max_hp: 10,
+                hp: 10,
+                base_defence: 0,
+                xp: 0,
+                base_power: 1,
+            },
+        );
+        id
+    }
+
+    #[test]
+    fn bolts_go_past_peaceful_shopkeepers() {
+        let game = Game {
+            map: vec![vec![Tile::empty(); 10]; 20],
+            ..Game::default()
+        };
+        let orc = {
+            let mut world = game.world.borrow_mut();
+            let player = fighter(&mut world, 5, "Tester");
+            world.set_player(player);
+            let shopkeeper = fighter(&mut world, 6, "shopkeeper");
+            world.ais.insert(
+                shopkeeper,
+                AI::Shopkeeper {
+                    shop: Rect::new(6, 4, 3, 3),
+                    post: (6, 5),
+                    aside: (6, 4),
+                },
+            );
+            let orc = fighter(&mut world, 8, "orc");
+            world.ais.insert(orc, AI::Basic);
+            orc
+        };
+
+        assert_eq!(monster_in_direction(&game, 1, 0, WAND_RANGE), Some(orc));
+        assert_eq!(monster_in_direction(&game, -1, 0, 4), None);
+    }
+
+    #[test]
+    fn eating_says_when_hunger_is_gone() {
+        let mut game = Game::default();
+        let (apple, ration) = {
+            let mut world = game.world.borrow_mut();
+            let player = fighter(&mut world, 5, "Tester");
+            world.set_player(player);
+            world.vitals.insert(
+                player,
+                Vitals {
+                    nutrition: 60,
+                    poisoned: 0,
+                },
+            );
+            let apple = world.spawn_at(5, 5, '%', Color::Food, false, "apple", false);
+            world.items.insert(apple, Item::Apple);
+            let ration = world.spawn_at(5, 5, '%', Color::Food, false, "ration", false);
+            world.items.insert(ration, Item::Ration);
+            (apple, ration)
+        };
+
+        // still hungry after the apple
+        eat(apple, &mut game);
+        assert!(game.statuses.iter().all(|s| !s.msg().contains("hungry")));
+
+        eat(ration, &mut game);
+        assert!(game
+            .statuses
+            .iter()
+            .any(|s| s.msg() == "You are no longer hungry."));
+    }
+}
@@ -0,0 +1,498 @@
+use crate::paths;
+
+use pancurses::Input;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+const KEYMAP_FILE: &str = "keymap";
+
+/// What a key can be bound to. Most of these turn straight into a command,
+/// the rest need a prompt first or are handled by the interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Move(i32, i32),
+    Run(i32, i32),
+    Travel,
+    Pickup,
+    Wait,
+    Rest,
+    Explore,
+    Overview,
+    Character,
+    Apply,
+    Drop,
+    Descend,
+    Help,
+    Quit,
+}
+
+/// every action, with its name in the keymap file and what the help screen says about it
+const ACTIONS: &[(&str, Action, &str)] = &[
+    ("move_north", Action::Move(0, -1), "move or attack north"),
+    ("move_south", Action::Move(0, 1), "move or attack south"),
+    ("move_west", Action::Move(-1, 0), "move or attack west"),
+    ("move_east", Action::Move(1, 0), "move or attack east"),
+    (
+        "move_northwest",
+        Action::Move(-1, -1),
+        "move or attack northwest",
+    ),
+    (
+        "move_northeast",
+        Action::Move(1, -1),
+        "move or attack northeast",
+    ),
+    (
+        "move_southwest",
+        Action::Move(-1, 1),
+        "move or attack southwest",
+    ),
+    (
+        "move_southeast",
+        Action::Move(1, 1),
+        "move or attack southeast",
+    ),
+    ("run_north", Action::Run(0, -1), "run north"),
+    ("run_south", Action::Run(0, 1), "run south"),
+    ("run_west", Action::Run(-1, 0), "run west"),
+    ("run_east", Action::Run(1, 0), "run east"),
+    ("run_northwest", Action::Run(-1, -1), "run northwest"),
+    ("run_northeast", Action::Run(1, -1), "run northeast"),
+    ("run_southwest", Action::Run(-1, 1), "run southwest"),
+    ("run_southeast", Action::Run(1, 1), "run southeast"),
+    (
+        "travel",
+        Action::Travel,
+        "travel to a place, or to the stairs",
+    ),
+    ("pickup", Action::Pickup, "pick an item up, or gold"),
+    ("wait", Action::Wait, "wait a turn"),
+    ("rest", Action::Rest, "rest until healed"),
+    (
+        "explore",
+        Action::Explore,
+        "explore until something comes up",
+    ),
+    ("overview", Action::Overview, "show the level overview"),
+    ("character", Action::Character, "show the character sheet"),
+    ("apply", Action::Apply, "apply (use) an item"),
+    ("drop", Action::Drop, "drop an item"),
+    ("descend", Action::Descend, "use the stairs"),
+    ("help", Action::Help, "show this list"),
+    ("quit", Action::Quit, "quit the game"),
+];
+
+/// the ready-made sets of bindings the keymap file starts from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    Vi,
+    Numpad,
+    Wasd,
+}
+
+impl Preset {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "vi" => Some(Preset::Vi),
+            "numpad" => Some(Preset::Numpad),
+            "wasd" => Some(Preset::Wasd),
+            _ => None,
+        }
+    }
+
+    /// the keys of an action, as they would be written in the keymap file
+    fn keys(self, action: &str) -> &'static str {
+        match (self, action) {
+            (Preset::Vi, "move_north") => "k Up",
+            (Preset::Vi, "move_south") => "j Down",
+            (Preset::Vi, "move_west") => "h Left",
+            (Preset::Vi, "move_east") => "l Right",
+            (Preset::Vi, "move_northwest") => "y",
+            (Preset::Vi, "move_northeast") => "u",
+            (Preset::Vi, "move_southwest") => "b",
+            (Preset::Vi, "move_southeast") => "n",
+            (Preset::Vi, "run_north") => "K ShiftUp",
+            (Preset::Vi, "run_south") => "J ShiftDown",
+            (Preset::Vi, "run_west") => "H ShiftLeft",
+            (Preset::Vi, "run_east") => "L ShiftRight",
+            (Preset::Vi, "run_northwest") => "Y",
+            (Preset::Vi, "run_northeast") => "U",
+            (Preset::Vi, "run_southwest") => "B",
+            (Preset::Vi, "run_southeast") => "N",
+
+            // with num lock off the keypad sends the cursor keys
+            (Preset::Numpad, "move_north") => "8 Up",
+            (Preset::Numpad, "move_south") => "2 Down",
+            (Preset::Numpad, "move_west") => "4 Left",
+            (Preset::Numpad, "move_east") => "6 Right",
+            (Preset::Numpad, "move_northwest") => "7 Home",
+            (Preset::Numpad, "move_northeast") => "9 PageUp",
+            (Preset::Numpad, "move_southwest") => "1 End",
+            (Preset::Numpad, "move_southeast") => "3 PageDown",
+            (Preset::Numpad, "wait") => "5 Center .",
+            (Preset::Numpad, "run_north") => "ShiftUp",
+            (Preset::Numpad, "run_south") => "ShiftDown",
+            (Preset::Numpad, "run_west") => "ShiftLeft",
+            (Preset::Numpad, "run_east") => "ShiftRight",
+
+            (Preset::Wasd, "move_north") => "w Up",
+            (Preset::Wasd, "move_south") => "s Down",
+            (Preset::Wasd, "move_west") => "a Left",
+            (Preset::Wasd, "move_east") => "d Right",
+            (Preset::Wasd, "move_northwest") => "q",
+            (Preset::Wasd, "move_northeast") => "e",
+            (Preset::Wasd, "move_southwest") => "z",
+            (Preset::Wasd, "move_southeast") => "c",
+            (Preset::Wasd, "run_north") => "W ShiftUp",
+            (Preset::Wasd, "run_south") => "S ShiftDown",
+            (Preset::Wasd, "run_west") => "A ShiftLeft",
+            (Preset::Wasd, "run_east") => "D ShiftRight",
+            // Q quits
+            (Preset::Wasd, "run_northeast") => "E",
+            (Preset::Wasd, "run_southwest") => "Z",
+            (Preset::Wasd, "run_southeast") => "C",
+            (Preset::Wasd, "pickup") => "g ,",
+            (Preset::Wasd, "wait") => "x .",
+            (Preset::Wasd, "apply") => "f",
+            (Preset::Wasd, "drop") => "v",
+            (Preset::Wasd, "quit") => "Q Delete",
+
+            // the rest is the same everywhere
+            (_, "pickup") => ",",
+            (_, "wait") => ".",
+            (_, "rest") => "R",
+            (_, "explore") => "o",
+            (_, "travel") => "_",
+            (_, "overview") => "M",
+            (_, "character") => "@",
+            (_, "apply") => "a",
+            (_, "drop") => "d",
+            (_, "descend") => ">",
+            (_, "help") => "?",
+            (_, "quit") => "q Delete",
+            _ => "",
+        }
+    }
+}
+
+/// Which key does what, read from the keymap file in the config directory.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    actions: HashMap<Input, Action>,
+    /// the keys left to each action, in the order of `ACTIONS`
+    keys: Vec<Vec<Input>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::parse("").0
+    }
+}
+
+impl Keymap {
+    /// the keymap from the config directory, along with whatever was wrong
+    /// with it; a template is written there if there is none yet
+    pub fn load() -> (Self, Vec<String>) {
+        let path = paths::config_dir().join(KEYMAP_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let _ = write_template();
+                (Self::default(), vec![])
+            }
+            Err(e) => (
+                Self::default(),
+                vec![format!("Couldn't read {}: {}", path.display(), e)],
+            ),
+        }
+    }
+
+    /// `preset = name` picks the starting bindings, and `action = keys` lines
+    /// replace the keys of one action
+    fn parse(contents: &str) -> (Self, Vec<String>) {
+        let mut problems = vec![];
+        let mut preset = Preset::Vi;
+        let mut overrides = vec![];
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => {
+                    problems.push(format!("line {}: expected `action = keys`", number + 1));
+                    continue;
+                }
+            };
+            if name == "preset" {
+                match Preset::parse(value) {
+                    Some(chosen) => preset = chosen,
+                    None => problems.push(format!(
+                        "line {}: unknown preset {}, use vi, numpad or wasd",
+                        number + 1,
+                        value
+                    )),
+                }
+            } else if let Some(index) = ACTIONS.iter().position(|(action, _, _)| *action == name) {
+                overrides.push((index, number, value));
+            } else {
+                problems.push(format!("line {}: unknown action {}", number + 1, name));
+            }
+        }
+
+        let mut keys = ACTIONS
+            .iter()
+            .map(|(name, _, _)| parse_keys(preset.keys(name)))
+            .collect::<Vec<_>>();
+        for &(index, number, value) in &overrides {
+            keys[index] = parse_keys(value);
+            for name in value.split_whitespace() {
+                if parse_key(name).is_none() {
+                    problems.push(format!("line {}: unknown key {}", number + 1, name));
+                }
+            }
+        }
+
+        // keys the player bound themselves win over the preset's
+        let overridden = overrides
+            .iter()
+            .map(|&(index, _, _)| index)
+            .collect::<Vec<_>>();
+        let order = overridden
+            .iter()
+            .copied()
+            .chain((0..ACTIONS.len()).filter(|index| !overridden.contains(index)));
+
+        let mut owners: HashMap<Input, usize> = HashMap::new();
+        for index in order {
+            for key in &keys[index] {
+                match owners.get(key) {
+                    Some(&owner) if owner != index => problems.push(format!(
+                        "{} is bound to both {} and {}, it stays on {}",
+                        key_name(key),
+                        ACTIONS[owner].0,
+                        ACTIONS[index].0,
+                        ACTIONS[owner].0
+                    )),
+                    _ => {
+                        owners.insert(*key, index);
+                    }
+                }
+            }
+        }
+        for (index, action_keys) in keys.iter_mut().enumerate() {
+            action_keys.retain(|key| owners.get(key) == Some(&index));
+        }
+
+        let actions = owners
+            .into_iter()
+            .map(|(key, index)| (key, ACTIONS[index].1))
+            .collect();
+        (Self { actions, keys }, problems)
+    }
+
+    pub fn action(&self, key: &Input) -> Option<Action> {
+        self.actions.get(key).copied()
+    }
+
+    /// the keys bound to an action
+    pub fn keys(&self, action: Action) -> &[Input] {
+        ACTIONS
+            .iter()
+            .position(|&(_, bound, _)| bound == action)
+            .map_or(&[], |index| &self.keys[index])
+    }
+
+    /// one line per action with the keys bound to it
+    pub fn help(&self) -> Vec<String> {
+        ACTIONS
+            .iter()
+            .zip(&self.keys)
+            .map(|((_, _, description), keys)| {
+                let keys = if keys.is_empty() {
+                    "(unbound)".to_string()
+                } else {
+                    keys.iter().map(key_name).collect::<Vec<_>>().join(", ")
+                };
+                format!("{:<16} {}", keys, description)
+            })
+            .collect()
+    }
+}
+
+/// the keys that could be read from a space separated list of key names
+fn parse_keys(names: &str) -> Vec<Input> {
+    names.split_whitespace().filter_map(parse_key).collect()
+}
+
+fn parse_key(name: &str) -> Option<Input> {
+    let key = match name {
+        "Up" => Input::KeyUp,
+        "Down" => Input::KeyDown,
+        "Left" => Input::KeyLeft,
+        "Right" => Input::KeyRight,
+        "ShiftUp" => Input::KeySR,
+        "ShiftDown" => Input::KeySF,
+        "ShiftLeft" => Input::KeySLeft,
+        "ShiftRight" => Input::KeySRight,
+        "Home" => Input::KeyHome,
+        "End" => Input::KeyEnd,
+        "PageUp" => Input::KeyPPage,
+        "PageDown" => Input::KeyNPage,
+        "Center" => Input::KeyB2,
+        "Delete" => Input::KeyDC,
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Input::Character(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+fn key_name(key: &Input) -> String {
+    match key {
+        Input::KeyUp => "Up".to_string(),
+        Input::KeyDown => "Down".to_string(),
+        Input::KeyLeft => "Left".to_string(),
+        Input::KeyRight => "Right".to_string(),
+        Input::KeySR => "ShiftUp".to_string(),
+        Input::KeySF => "ShiftDown".to_string(),
+        Input::KeySLeft => "ShiftLeft".to_string(),
+        Input::KeySRight => "ShiftRight".to_string(),
+        Input::KeyHome => "Home".to_string(),
+        Input::KeyEnd => "End".to_string(),
+        Input::KeyPPage => "PageUp".to_string(),
+        Input::KeyNPage => "PageDown".to_string(),
+        Input::KeyB2 => "Center".to_string(),
+        Input::KeyDC => "Delete".to_string(),
+        Input::Character(c) => c.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// a keymap file that spells out the default bindings, for the player to edit
+fn write_template() -> io::Result<()> {
+    let mut contents = String::from(
+        "# roguelike-rs key bindings\n\
+         # The preset is vi, numpad or wasd. Uncomment a line below to give an\n\
+         # action other keys than the preset does, separated by spaces.\n\
+         preset = vi\n\n",
+    );
+    for (name, _, _) in ACTIONS {
+        contents += &format!("# {} = {}\n", name, Preset::Vi.keys(name));
+    }
+    fs::create_dir_all(paths::config_dir())?;
+    fs::write(paths::config_dir().join(KEYMAP_FILE), contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(keymap: &Keymap, key: char) -> Option<Action> {
+        keymap.action(&Input::Character(key))
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset in ["vi", "numpad", "wasd"] {
+            let (_, problems) = Keymap::parse(&format!("preset = {}", preset));
+            assert_eq!(problems, Vec::<String>::new(), "preset {}", preset);
+        }
+    }
+
+    #[test]
+    fn vi_is_the_default() {
+        let (keymap, problems) = Keymap::parse("");
+        assert!(problems.is_empty());
+        assert_eq!(action(&keymap, 'k'), Some(Action::Move(0, -1)));
+        assert_eq!(action(&keymap, 'Y'), Some(Action::Run(-1, -1)));
+        assert_eq!(keymap.action(&Input::KeyUp), Some(Action::Move(0, -1)));
+        assert_eq!(action(&keymap, 'q'), Some(Action::Quit));
+        assert_eq!(action(&keymap, 'w'), None);
+    }
+
+    #[test]
+    fn wasd_moves_what_vi_leaves_alone() {
+        let (keymap, _) = Keymap::parse("preset = wasd");
+        assert_eq!(action(&keymap, 'w'), Some(Action::Move(0, -1)));
+        assert_eq!(action(&keymap, 'q'), Some(Action::Move(-1, -1)));
+        assert_eq!(action(&keymap, 'Q'), Some(Action::Quit));
+        assert_eq!(action(&keymap, 'a'), Some(Action::Move(-1, 0)));
+        assert_eq!(action(&keymap, 'f'), Some(Action::Apply));
+        assert_eq!(action(&keymap, 'k'), None);
+    }
+
+    #[test]
+    fn numpad_uses_the_keypad() {
+        let (keymap, _) = Keymap::parse("preset = numpad");
+        assert_eq!(action(&keymap, '8'), Some(Action::Move(0, -1)));
+        assert_eq!(keymap.action(&Input::KeyHome), Some(Action::Move(-1, -1)));
+        assert_eq!(keymap.action(&Input::KeyB2), Some(Action::Wait));
+        assert_eq!(action(&keymap, 'k'), None);
+    }
+
+    #[test]
+    fn overrides_replace_the_keys_of_an_action() {
+        let (keymap, problems) = Keymap::parse("# comment\n\nrest = Z\n");
+        assert!(problems.is_empty());
+        assert_eq!(action(&keymap, 'Z'), Some(Action::Rest));
+        assert_eq!(action(&keymap, 'R'), None);
+    }
+
+    #[test]
+    fn overrides_win_over_preset_keys() {
+        let (keymap, problems) = Keymap::parse("wait = k");
+        assert_eq!(action(&keymap, 'k'), Some(Action::Wait));
+        assert_eq!(keymap.action(&Input::KeyUp), Some(Action::Move(0, -1)));
+        assert_eq!(
+            problems,
+            vec!["k is bound to both wait and move_north, it stays on wait"]
+        );
+        assert!(keymap.help()[0].starts_with("Up "));
+        assert_eq!(keymap.keys(Action::Move(0, -1)), &[Input::KeyUp]);
+    }
+
+    #[test]
+    fn conflicting_overrides_keep_the_first() {
+        let (keymap, problems) = Keymap::parse("rest = x\ndrop = x");
+        assert_eq!(action(&keymap, 'x'), Some(Action::Rest));
+        assert_eq!(
+            problems,
+            vec!["x is bound to both rest and drop, it stays on rest"]
+        );
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+        let (keymap, problems) =
+            Keymap::parse("preset = emacs\nfly = f\nrest = R Hyper\njust some words");
+        assert_eq!(
+            problems,
+            vec![
+                "line 1: unknown preset emacs, use vi, numpad or wasd",
+                "line 2: unknown action fly",
+                "line 4: expected `action = keys`",
+                "line 3: unknown key Hyper",
+            ]
+        );
+        // what could be read still applies
+        assert_eq!(action(&keymap, 'R'), Some(Action::Rest));
+        assert_eq!(action(&keymap, 'k'), Some(Action::Move(0, -1)));
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for name in ["k", "@", "Up", "ShiftLeft", "PageDown", "Center", "Delete"] {
+            let key = parse_key(name).unwrap();
+            assert_eq!(key_name(&key), name);
+        }
+        assert_eq!(parse_key("kk"), None);
+    }
+}
@@ -2,13 +2,32 @@
 
 extern crate pancurses;
 extern crate rand;
+extern crate serde;
+extern crate serde_json;
 
 pub mod ai;
+pub mod character;
+pub mod command;
 pub mod curses;
 pub mod fov;
 pub mod game;
+pub mod highscore;
+pub mod input;
 pub mod item;
+pub mod keymap;
 pub mod map_gen;
+pub mod menu;
+pub mod morgue;
 pub mod object;
 pub mod object_gen;
+pub mod options;
+pub mod overview;
+pub mod pathfind;
+pub mod paths;
+pub mod rng;
+pub mod save;
+pub mod shop;
+pub mod store;
+pub mod theme;
 pub mod tile;
+pub mod world;
@@ -1,6 +1,12 @@
 extern crate roguelike_rs;
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--scores") {
+        roguelike_rs::highscore::print_top();
+        return;
+    }
+
+    let mut graphics = roguelike_rs::curses::Graphics::default();
     let mut game = roguelike_rs::game::Game::default();
-    game.start();
+    roguelike_rs::menu::run(&mut game, &mut graphics);
 }
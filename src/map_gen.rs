@@ -1,12 +1,14 @@
 use std::cmp;
 
-use crate::tile::{Map, Tile};
+use crate::tile::{self, Map, Tile};
+use serde::{Deserialize, Serialize};
 
 pub const ROOM_MAX_SIZE: i32 = 10;
 pub const ROOM_MIN_SIZE: i32 = 6;
+/// for a map the size of the first level; bigger maps get more
 pub const MAX_ROOMS: i32 = 30;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Rect {
     pub x1: i32,
     pub y1: i32,
@@ -29,6 +31,11 @@ impl Rect {
         (center_x, center_y)
     }
 
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        // true if (x, y) is one of the room's floor tiles
+        x >= self.x1 && x < self.x2 && y >= self.y1 && y < self.y2
+    }
+
     pub fn intersects_with(&self, other: &Rect) -> bool {
         // returns true if this rectangle intersects with another one
         (self.x1 <= other.x2)
@@ -60,3 +67,16 @@ pub fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
         map[x as usize][y as usize] = Tile::empty();
     }
 }
+
+pub fn dig(x: i32, y: i32, dx: i32, dy: i32, length: i32, map: &mut Map) {
+    // straight tunnel from (x, y) in a direction, never breaking the outer walls
+    let (mut x, mut y) = (x, y);
+    for _ in 0..length {
+        x += dx;
+        y += dy;
+        if x < 1 || y < 1 || x >= tile::width(map) - 1 || y >= tile::height(map) - 1 {
+            break;
+        }
+        map[x as usize][y as usize] = Tile::empty();
+    }
+}
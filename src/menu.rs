@@ -0,0 +1,129 @@
+use crate::curses::{Graphics, Menu};
+use crate::game::Game;
+use crate::highscore;
+use crate::keymap::Keymap;
+use crate::save;
+use crate::theme::Theme;
+
+const TITLE: &str = "roguelike-rs";
+const NAME_MAX_LEN: usize = 20;
+const DEFAULT_NAME: &str = "Adventurer";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Choice {
+    NewGame,
+    Continue,
+    HighScores,
+    Options,
+    Quit,
+}
+
+impl Choice {
+    fn label(self) -> &'static str {
+        match self {
+            Choice::NewGame => "New game",
+            Choice::Continue => "Continue",
+            Choice::HighScores => "High scores",
+            Choice::Options => "Options",
+            Choice::Quit => "Quit",
+        }
+    }
+}
+
+/// the title screen, shown until the player quits
+pub fn run(game: &mut Game, graphics: &mut Graphics) {
+    graphics.line_walls = game.options.line_walls;
+    graphics.set_theme(Theme::load(&game.options.theme));
+    let (keymap, problems) = Keymap::load();
+    game.keymap = keymap;
+    if !problems.is_empty() {
+        graphics.show_text_screen("Problems with the keymap file", &problems);
+    }
+
+    let mut notice = String::new();
+
+    loop {
+        let mut choices = vec![Choice::NewGame];
+        if save::exists() {
+            choices.push(Choice::Continue);
+        }
+        choices.extend([Choice::HighScores, Choice::Options, Choice::Quit]);
+
+        let menu = choices
+            .iter()
+            .fold(Menu::new(TITLE), |menu, choice| match choice {
+                Choice::Quit => menu.keyed('q', choice.label()),
+                _ => menu.option(choice.label()),
+            });
+        graphics.window.clear();
+        let bottom = graphics.layout().bottom();
+        graphics.window.mvaddstr(bottom - 1, 2, &notice);
+        notice.clear();
+
+        match graphics.choose(&menu).map(|index| choices[index]) {
+            Some(Choice::NewGame) => {
+                if save::exists()
+                    && !graphics.ask_yes_no("This abandons your saved game. Are you sure?")
+                {
+                    continue;
+                }
+                if let Some(name) = ask_name(graphics) {
+                    if let Err(e) = save::delete() {
+                        notice = format!("Couldn't delete the old save: {}", e);
+                        continue;
+                    }
+                    game.new_game(graphics, &name);
+                }
+            }
+            Some(Choice::Continue) => {
+                if let Err(e) = game.continue_game(graphics) {
+                    notice = format!("Couldn't load the saved game: {}", e);
+                }
+            }
+            Some(Choice::HighScores) => {
+                let lines = highscore::load()
+                    .iter()
+                    .take(highscore::TOP_SHOWN)
+                    .enumerate()
+                    .map(|(i, entry)| format!("{:>2}. {}", i + 1, entry))
+                    .collect::<Vec<_>>();
+                graphics.show_text_screen("Top scores", &lines);
+            }
+            Some(Choice::Options) => {
+                if let Err(e) = options(game, graphics) {
+                    notice = format!("Couldn't save the options: {}", e);
+                }
+            }
+            Some(Choice::Quit) => break,
+            None => {}
+        }
+    }
+}
+
+fn ask_name(graphics: &Graphics) -> Option<String> {
+    let name = graphics.ask_line("What is your name, adventurer?", NAME_MAX_LEN)?;
+    if name.is_empty() {
+        Some(DEFAULT_NAME.to_string())
+    } else {
+        Some(name)
+    }
+}
+
+/// toggle options until the player goes back, then store them
+fn options(game: &mut Game, graphics: &mut Graphics) -> std::io::Result<()> {
+    graphics.window.clear();
+    loop {
+        let menu = game
+            .options
+            .describe()
+            .iter()
+            .fold(Menu::new("Options"), |menu, label| menu.option(label));
+        match graphics.choose(&menu) {
+            Some(index) => game.options.toggle(index),
+            None => break,
+        }
+    }
+    graphics.line_walls = game.options.line_walls;
+    graphics.set_theme(Theme::load(&game.options.theme));
+    game.options.save()
+}
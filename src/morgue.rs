@@ -0,0 +1,160 @@
+use crate::game::{describe_item, Game};
+use crate::object;
+use crate::paths;
+use crate::tile;
+
+use std::fmt;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MORGUE_DIR: &str = "morgue";
+const LOG_LINES: usize = 30;
+
+/// write a plain-text dump of the run, returning where it was put
+pub fn write(game: &Game, outcome: &str) -> io::Result<PathBuf> {
+    let dir = paths::data_dir().join(MORGUE_DIR);
+    fs::create_dir_all(&dir)?;
+
+    // the name is the player's choice, keep only what is safe in a file name
+    let world = game.world.borrow();
+    let name = world
+        .name(world.player_id())
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = dir.join(format!("{}-{}.txt", name, timestamp));
+
+    drop(world);
+    let contents = dump(game, outcome).map_err(io::Error::other)?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+fn dump(game: &Game, outcome: &str) -> Result<String, fmt::Error> {
+    let world = game.world.borrow();
+    let player_id = world.player_id();
+    let fighter = world.fighters[player_id];
+    let experience = world.experience[player_id];
+    let vitals = world.vitals[player_id];
+    let mut out = String::new();
+
+    writeln!(out, "roguelike-rs character dump")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} {} on dungeon level {} after {} turns.",
+        world.name(player_id),
+        outcome,
+        game.dungeon_level,
+        game.turns
+    )?;
+    writeln!(out, "Seed: {}", game.seed)?;
+    writeln!(out, "Score: {}", game.score())?;
+    writeln!(out)?;
+
+    writeln!(out, "== Stats ==")?;
+    writeln!(out, "Level: {}", experience.level)?;
+    writeln!(out, "XP: {}/{}", experience.xp, experience.level_up_xp)?;
+    writeln!(
+        out,
+        "HP: {}/{} (base {})",
+        fighter.hp,
+        object::max_hp(&world, player_id),
+        fighter.base_max_hp
+    )?;
+    writeln!(
+        out,
+        "Power: {} (base {})",
+        object::power(&world, player_id),
+        fighter.base_power
+    )?;
+    writeln!(
+        out,
+        "Defence: {} (base {})",
+        object::defence(&world, player_id),
+        fighter.base_defence
+    )?;
+    writeln!(out, "Hunger: {}", vitals.hunger())?;
+    writeln!(out, "Gold: {}", world.gold.get(player_id).unwrap_or(&0))?;
+    writeln!(out)?;
+
+    writeln!(out, "== Inventory ==")?;
+    let inventory = world.inventory(player_id);
+    if inventory.is_empty() {
+        writeln!(out, "(empty)")?;
+    }
+    for (i, &item) in inventory.iter().enumerate() {
+        let label = describe_item(&world, item);
+        writeln!(out, "{} - {}", (i + 97) as u8 as char, label)?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "== Kills ==")?;
+    let kills = game.kills_by_count();
+    if kills.is_empty() {
+        writeln!(out, "(none)")?;
+    }
+    for (monster, count) in kills {
+        writeln!(out, "{:>4} {}", count, monster)?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "== Last messages ==")?;
+    let pending = game.statuses.iter().map(|status| status.msg());
+    let messages = game
+        .log
+        .iter()
+        .map(|msg| msg.as_str())
+        .chain(pending)
+        .collect::<Vec<_>>();
+    for msg in &messages[messages.len().saturating_sub(LOG_LINES)..] {
+        writeln!(out, "{}", msg)?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "== Map ==")?;
+    for y in 0..tile::height(&game.map) {
+        let mut line = (0..tile::width(&game.map))
+            .map(|x| {
+                let tile = &game.map[x as usize][y as usize];
+                if !tile.visible {
+                    ' '
+                } else if tile.block_sight {
+                    '+'
+                } else {
+                    '.'
+                }
+            })
+            .collect::<Vec<_>>();
+        // living things are drawn over items
+        let (alive, others): (Vec<_>, Vec<_>) = world
+            .renderables
+            .iter()
+            .filter_map(|(id, renderable)| world.positions.get(id).map(|p| (id, p, renderable)))
+            .partition(|(id, _, _)| world.is_alive(*id));
+        for (_, p, renderable) in others.into_iter().chain(alive) {
+            if p.y == y && game.map[p.x as usize][p.y as usize].currently_visible {
+                line[p.x as usize] = renderable.ch;
+            }
+        }
+        let (player_x, player_y) = world.pos(player_id);
+        if player_y == y {
+            line[player_x as usize] = world.renderables[player_id].ch;
+        }
+        writeln!(out, "{}", line.into_iter().collect::<String>().trim_end())?;
+    }
+
+    Ok(out)
+}
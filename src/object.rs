@@ -1,264 +1,415 @@
 use crate::ai::AI;
 use crate::curses::Status;
 use crate::item::{Equipment, Item, Slot};
+use crate::store::ObjectId;
+use crate::theme::{Color, Theme};
 use crate::tile::{is_blocked, Map};
+use crate::world::World;
 use pancurses::A_BOLD;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+const HUNGRY_NUTRITION: i32 = 150;
+const WEAK_NUTRITION: i32 = 50;
+const FAINTING_NUTRITION: i32 = 0;
+const STARVED_NUTRITION: i32 = -100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Fighter {
     pub hp: i32,
+    /// what killing it is worth
     pub xp: i32,
     pub base_power: i32,
     pub base_defence: i32,
     pub base_max_hp: i32,
 }
 
-#[derive(Clone)]
-pub struct Object {
-    pub x: i32,
-    pub y: i32,
-    pub ch: char,
-    color: i16,
-    is_bold: bool,
-    pub name: String,
-    pub blocks: bool,
-    pub alive: bool,
-    pub fighter: Option<Fighter>,
-    pub ai: Option<AI>,
-    pub item: Option<Item>,
-    pub level: i32,
-    pub level_up_xp: i32,
-    pub equipment: Option<Equipment>,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hunger {
+    NotHungry,
+    Hungry,
+    Weak,
+    Fainting,
+    Starved,
 }
 
-impl Object {
-    pub fn new(
-        x: i32,
-        y: i32,
-        ch: char,
-        color: i16,
-        is_bold: bool,
-        name: &str,
-        blocks: bool,
-    ) -> Self {
-        Self {
-            x,
-            y,
-            ch,
-            color,
-            is_bold,
-            blocks,
-            alive: false,
-            name: name.into(),
-            fighter: None,
-            ai: None,
-            item: None,
-            level: 1,
-            level_up_xp: 0,
-            equipment: None,
+impl std::fmt::Display for Hunger {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Hunger::NotHungry => write!(f, "Not hungry"),
+            Hunger::Hungry => write!(f, "Hungry"),
+            Hunger::Weak => write!(f, "Weak"),
+            Hunger::Fainting => write!(f, "Fainting"),
+            Hunger::Starved => write!(f, "Starved"),
         }
     }
+}
 
-    pub fn draw(&self, win: &pancurses::Window) {
-        win.color_set(self.color);
-        if self.is_bold {
-            win.attron(A_BOLD);
+/// what dealt a blow, remembered to tell how someone died
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DamageSource {
+    Attack(String),
+    Item(String),
+    Starvation,
+    Poison,
+}
+
+impl std::fmt::Display for DamageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DamageSource::Attack(name) => write!(f, "killed by {}", with_article(name)),
+            DamageSource::Item(name) => write!(f, "killed by {}", with_article(name)),
+            DamageSource::Starvation => write!(f, "starved to death"),
+            DamageSource::Poison => write!(f, "succumbed to poison"),
         }
+    }
+}
+
+fn with_article(name: &str) -> String {
+    match name.chars().next() {
+        Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => format!("an {}", name),
+        _ => format!("a {}", name),
+    }
+}
 
-        win.mvaddch(self.y, self.x, self.ch);
+/// where an object is on the map
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// how an object looks on the map
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Renderable {
+    pub ch: char,
+    pub color: Color,
+    pub is_bold: bool,
+}
 
+impl Renderable {
+    /// draw it at a position on the screen
+    pub fn draw(&self, x: i32, y: i32, win: &pancurses::Window, theme: &Theme) {
+        let mut attributes = theme.attributes(self.color);
         if self.is_bold {
-            win.attroff(A_BOLD);
+            attributes |= A_BOLD;
         }
-        win.color_set(pancurses::COLOR_WHITE);
+
+        win.attron(attributes);
+        win.mvaddch(y, x, self.ch);
+        win.attroff(attributes);
     }
+}
 
-    pub fn pos(&self) -> (i32, i32) {
-        (self.x, self.y)
+/// character levels, for whoever grows stronger from their kills
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+    pub level_up_xp: i32,
+}
+
+/// food and sickness, for whoever needs to eat
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vitals {
+    pub nutrition: i32,
+    pub poisoned: i32,
+}
+
+impl Vitals {
+    pub fn hunger(&self) -> Hunger {
+        match self.nutrition {
+            n if n <= STARVED_NUTRITION => Hunger::Starved,
+            n if n <= FAINTING_NUTRITION => Hunger::Fainting,
+            n if n <= WEAK_NUTRITION => Hunger::Weak,
+            n if n <= HUNGRY_NUTRITION => Hunger::Hungry,
+            _ => Hunger::NotHungry,
+        }
     }
+}
 
-    pub fn set_pos(&mut self, x: i32, y: i32) {
-        self.x = x;
-        self.y = y;
+/// returns the xp the object was worth if this killed it
+pub fn take_damage(
+    world: &mut World,
+    id: ObjectId,
+    damage: i32,
+    source: DamageSource,
+    statuses: &mut Vec<Status>,
+) -> Option<i32> {
+    // apply damage if possible; the dead can't die a second time
+    let fighter = world.fighters.get_mut(id).filter(|f| f.hp > 0)?;
+    if damage > 0 {
+        fighter.hp -= damage;
     }
+    let (hp, xp) = (fighter.hp, fighter.xp);
 
-    /// return the distance to another object
-    pub fn distance_to(&self, other: &Object) -> f32 {
-        let dx = other.x - self.x;
-        let dy = other.y - self.y;
-        ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
+    // getting hurt wakes sleeping monsters up
+    if let Some(AI::Asleep { prev_ai, .. }) = world.ais.get(id) {
+        let prev_ai = *prev_ai.clone();
+        world.ais.insert(id, prev_ai);
     }
 
-    pub fn take_damage(&mut self, damage: i32, statuses: &mut Vec<Status>) -> Option<i32> {
-        // apply damage if possible
-        if let Some(fighter) = self.fighter.as_mut() {
-            if damage > 0 {
-                fighter.hp -= damage;
-            }
-
-            if fighter.hp < 0 {
-                statuses.push(Status::new(format!("{} is dead!", self.name), 1));
-
-                self.alive = false;
-                self.ch = '%';
-                self.color = pancurses::COLOR_RED;
-                self.blocks = false;
-                self.ai = None;
-                self.name = format!("remains of {}", self.name);
-
-                return Some(fighter.xp);
-            }
+    if hp <= 0 {
+        let color = if id == world.player_id() {
+            Color::Danger
+        } else {
+            Color::Good
+        };
+        statuses.push(Status::new(format!("{} is dead!", world.name(id)), 1).with_color(color));
+
+        if let Some(renderable) = world.renderables.get_mut(id) {
+            renderable.ch = '%';
+            renderable.color = Color::Corpse;
+        }
+        world.blockers.remove(id);
+        world.killed_by.insert(id, source);
+        world.release_stock(id);
+        if world.ais.remove(id).is_some() {
+            // monster remains can be picked up and eaten
+            world.items.insert(id, Item::Corpse);
+            let name = format!("remains of {}", world.name(id));
+            world.names.insert(id, name);
         }
 
-        None
+        return Some(xp);
     }
 
-    pub fn attack(&mut self, target: &mut Object, statuses: &mut Vec<Status>, inv: &Vec<Object>) {
-        let damage = self.power(inv) - target.defence(inv);
-        if damage > 0 {
-            statuses.push(Status::new(
-                format!("{} attacks {} for {} hp.", self.name, target.name, damage),
-                1,
-            ));
-            if let Some(xp) = target.take_damage(damage, statuses) {
-                self.fighter.as_mut().unwrap().xp += xp;
-            }
-        } else {
-            statuses.push(Status::new(
-                format!("{} attacks {}, but has no effect.", self.name, target.name),
+    None
+}
+
+/// returns true if the target was killed
+pub fn attack(
+    world: &mut World,
+    attacker: ObjectId,
+    target: ObjectId,
+    statuses: &mut Vec<Status>,
+) -> bool {
+    let damage = power(world, attacker) - defence(world, target);
+    let name = world.name(attacker).to_string();
+    let target_name = world.name(target).to_string();
+    // blows the player takes stand out
+    let color = if target == world.player_id() {
+        Color::Danger
+    } else {
+        Color::Message
+    };
+    if damage > 0 {
+        statuses.push(
+            Status::new(
+                format!("{} attacks {} for {} hp.", name, target_name, damage),
                 1,
-            ));
+            )
+            .with_color(color),
+        );
+        if let Some(xp) = take_damage(world, target, damage, DamageSource::Attack(name), statuses) {
+            gain_xp(world, attacker, xp);
+            return true;
         }
+    } else {
+        statuses.push(Status::new(
+            format!("{} attacks {}, but has no effect.", name, target_name),
+            1,
+        ));
     }
+    false
+}
 
-    pub fn heal(&mut self, amount: i32) {
-        if let Some(ref mut fighter) = self.fighter {
-            fighter.hp += amount;
-            if fighter.hp > fighter.base_max_hp {
-                fighter.hp = fighter.base_max_hp;
-            }
-        }
-    }
-    pub fn power(&self, inv: &Vec<Object>) -> i32 {
-        let base_power = self.fighter.map_or(0, |f| f.base_power);
-        let bonus: i32 = self
-            .get_all_equipped(inv)
-            .iter()
-            .map(|e| e.power_bonus)
-            .sum();
-        base_power + bonus
+/// only objects with experience get anything out of a kill
+pub fn gain_xp(world: &mut World, id: ObjectId, xp: i32) {
+    if let Some(experience) = world.experience.get_mut(id) {
+        experience.xp += xp;
     }
+}
 
-    pub fn get_all_equipped(&self, inv: &Vec<Object>) -> Vec<Equipment> {
-        if self.name == "player" {
-            inv.iter()
-                .filter(|item| item.equipment.map_or(false, |e| e.equipped))
-                .map(|item| item.equipment.unwrap())
-                .collect()
-        } else {
-            vec![] // other objects have no equipment
+pub fn heal(world: &mut World, id: ObjectId, amount: i32) {
+    // the dead stay dead
+    if let Some(fighter) = world.fighters.get_mut(id).filter(|f| f.hp > 0) {
+        fighter.hp += amount;
+        if fighter.hp > fighter.base_max_hp {
+            fighter.hp = fighter.base_max_hp;
         }
     }
+}
 
-    pub fn defence(&self, inv: &Vec<Object>) -> i32 {
-        let base_defence = self.fighter.map_or(0, |f| f.base_defence);
-        let bonus: i32 = self
-            .get_all_equipped(inv)
-            .iter()
-            .map(|e| e.defense_bonus)
-            .sum();
-        base_defence + bonus
-    }
+fn get_all_equipped(world: &World, owner: ObjectId) -> impl Iterator<Item = &Equipment> {
+    world
+        .inventory(owner)
+        .iter()
+        .filter_map(move |item| world.equipment.get(*item))
+        .filter(|equipment| equipment.equipped)
+}
 
-    pub fn max_hp(&self, inv: &Vec<Object>) -> i32 {
-        let base_max_hp = self.fighter.map_or(0, |f| f.base_max_hp);
-        let bonus: i32 = self
-            .get_all_equipped(inv)
-            .iter()
-            .map(|e| e.max_hp_bonus)
-            .sum();
-        base_max_hp + bonus
-    }
+pub fn power(world: &World, id: ObjectId) -> i32 {
+    let base_power = world.fighters.get(id).map_or(0, |f| f.base_power);
+    let bonus: i32 = get_all_equipped(world, id).map(|e| e.power_bonus).sum();
+    base_power + bonus
+}
 
-    pub fn equip(&mut self, statuses: &mut Vec<Status>) {
-        if self.item.is_none() {
-            statuses.push(Status::new(
-                format!("Can't equip {} because it's not an Item.", self.name),
-                1,
-            ));
-            return;
-        };
-        if let Some(ref mut equipment) = self.equipment {
-            if !equipment.equipped {
-                equipment.equipped = true;
-                statuses.push(Status::new(
-                    format!("Equipped {} on {}.", self.name, equipment.slot),
-                    1,
-                ));
-            }
-        } else {
+pub fn defence(world: &World, id: ObjectId) -> i32 {
+    let base_defence = world.fighters.get(id).map_or(0, |f| f.base_defence);
+    let bonus: i32 = get_all_equipped(world, id).map(|e| e.defense_bonus).sum();
+    base_defence + bonus
+}
+
+pub fn max_hp(world: &World, id: ObjectId) -> i32 {
+    let base_max_hp = world.fighters.get(id).map_or(0, |f| f.base_max_hp);
+    let bonus: i32 = get_all_equipped(world, id).map(|e| e.max_hp_bonus).sum();
+    base_max_hp + bonus
+}
+
+pub fn equip(world: &mut World, id: ObjectId, statuses: &mut Vec<Status>) {
+    let name = world.name(id).to_string();
+    if !world.items.contains(id) {
+        statuses.push(Status::new(
+            format!("Can't equip {} because it's not an Item.", name),
+            1,
+        ));
+        return;
+    };
+    if let Some(equipment) = world.equipment.get_mut(id) {
+        if !equipment.equipped {
+            equipment.equipped = true;
             statuses.push(Status::new(
-                format!("Can't equip {} because it's not an Equipment.", self.name),
+                format!("Equipped {} on {}.", name, equipment.slot),
                 1,
             ));
         }
+    } else {
+        statuses.push(Status::new(
+            format!("Can't equip {} because it's not an Equipment.", name),
+            1,
+        ));
     }
+}
 
-    pub fn dequip(&mut self, statuses: &mut Vec<Status>) {
-        if self.item.is_none() {
+pub fn dequip(world: &mut World, id: ObjectId, statuses: &mut Vec<Status>) {
+    let name = world.name(id).to_string();
+    if !world.items.contains(id) {
+        statuses.push(Status::new(
+            format!("Can't dequip {} because it's not an Item.", name),
+            1,
+        ));
+        return;
+    };
+    if let Some(equipment) = world.equipment.get_mut(id) {
+        if equipment.equipped {
+            equipment.equipped = false;
             statuses.push(Status::new(
-                format!("Can't dequip {} because it's not an Item.", self.name),
-                1,
-            ));
-            return;
-        };
-        if let Some(ref mut equipment) = self.equipment {
-            if equipment.equipped {
-                equipment.equipped = false;
-                statuses.push(Status::new(
-                    format!("Dequipped {} from {}.", self.name, equipment.slot),
-                    1,
-                ));
-            }
-        } else {
-            statuses.push(Status::new(
-                format!("Can't dequip {} because it's not an Equipment.", self.name),
+                format!("Dequipped {} from {}.", name, equipment.slot),
                 1,
             ));
         }
+    } else {
+        statuses.push(Status::new(
+            format!("Can't dequip {} because it's not an Equipment.", name),
+            1,
+        ));
     }
 }
 
-pub fn get_equipped_in_slot(slot: Slot, inventory: &[Object]) -> Option<usize> {
-    for (inventory_id, item) in inventory.iter().enumerate() {
-        if item
+pub fn get_equipped_in_slot(world: &World, owner: ObjectId, slot: Slot) -> Option<ObjectId> {
+    world.inventory(owner).iter().copied().find(|item| {
+        world
             .equipment
-            .as_ref()
-            .map_or(false, |e| e.equipped && e.slot == slot)
-        {
-            return Some(inventory_id);
+            .get(*item)
+            .is_some_and(|e| e.equipped && e.slot == slot)
+    })
+}
+
+/// scatter the inventory of dead monsters on the floor where they died
+pub fn drop_loot(world: &mut World, statuses: &mut Vec<Status>) {
+    // the player's things stay in their pack, to be looked at after death
+    let player_id = world.player_id();
+    for owner in world.inventories.ids() {
+        if owner == player_id || world.is_alive(owner) || !world.positions.contains(owner) {
+            continue;
+        }
+        while !world.inventory(owner).is_empty() {
+            let item = world.drop_item(owner, 0);
+            statuses.push(Status::new(
+                format!("A {} falls to the floor.", world.name(item)),
+                1,
+            ));
         }
     }
-    None
 }
 
-pub fn move_by(id: usize, dx: i32, dy: i32, map: &Map, objects: &mut [Object]) {
-    let (x, y) = objects[id].pos();
-    if !is_blocked(x + dx, y + dy, map, objects) {
-        objects[id].set_pos(x + dx, y + dy);
+pub fn move_by(world: &mut World, id: ObjectId, dx: i32, dy: i32, map: &Map) {
+    let (x, y) = world.pos(id);
+    if !is_blocked(x + dx, y + dy, map, world) {
+        world.set_pos(id, x + dx, y + dy);
     }
 }
 
 /// will cause an object (monster, usually) to move towards a position (the player’s coordinates, usually).
-pub fn move_towards(id: usize, target_x: i32, target_y: i32, map: &Map, objects: &mut [Object]) {
+pub fn move_towards(world: &mut World, id: ObjectId, target_x: i32, target_y: i32, map: &Map) {
     // vector from this object to the target, and distance
-    let dx = target_x - objects[id].x;
-    let dy = target_y - objects[id].y;
+    let (x, y) = world.pos(id);
+    let dx = target_x - x;
+    let dy = target_y - y;
     let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
 
     // normalize it to length 1 (preserving direction), then round it and
     // convert to integer so the movement is restricted to the map grid
     let dx = (dx as f32 / distance).round() as i32;
     let dy = (dy as f32 / distance).round() as i32;
-    move_by(id, dx, dy, map, objects);
+    move_by(world, id, dx, dy, map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunger(nutrition: i32) -> Hunger {
+        Vitals {
+            nutrition,
+            poisoned: 0,
+        }
+        .hunger()
+    }
+
+    #[test]
+    fn hunger_thresholds() {
+        assert_eq!(hunger(HUNGRY_NUTRITION + 1), Hunger::NotHungry);
+        assert_eq!(hunger(HUNGRY_NUTRITION), Hunger::Hungry);
+        assert_eq!(hunger(WEAK_NUTRITION + 1), Hunger::Hungry);
+        assert_eq!(hunger(WEAK_NUTRITION), Hunger::Weak);
+        assert_eq!(hunger(FAINTING_NUTRITION + 1), Hunger::Weak);
+        assert_eq!(hunger(FAINTING_NUTRITION), Hunger::Fainting);
+        assert_eq!(hunger(STARVED_NUTRITION + 1), Hunger::Fainting);
+        assert_eq!(hunger(STARVED_NUTRITION), Hunger::Starved);
+        assert_eq!(hunger(i32::MIN), Hunger::Starved);
+    }
+
+    #[test]
+    fn the_dead_only_die_once() {
+        let mut world = World::default();
+        let player = world.spawn_at(0, 0, '@', Color::Player, true, "Tester", true);
+        world.set_player(player);
+        world.fighters.insert(
+            player,
+            Fighter {
+                base_max_hp: 10,
+                hp: 10,
+                base_defence: 0,
+                xp: 0,
+                base_power: 1,
+            },
+        );
+        let mut statuses = vec![];
+        let orc = DamageSource::Attack("orc".to_string());
+        assert_eq!(
+            take_damage(&mut world, player, 10, orc, &mut statuses),
+            Some(0)
+        );
+        assert_eq!(
+            take_damage(&mut world, player, 1, DamageSource::Poison, &mut statuses),
+            None
+        );
+
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(
+            world.killed_by.get(player).map(|source| source.to_string()),
+            Some("killed by an orc".to_string())
+        );
+    }
 }
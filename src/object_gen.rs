@@ -1,17 +1,83 @@
 use crate::ai::AI;
 use crate::item::{Equipment, Item, Slot};
 use crate::map_gen::Rect;
-use crate::object::{Fighter, Object};
+use crate::object::Fighter;
+use crate::rng;
+use crate::store::ObjectId;
+use crate::theme::Color;
 use crate::tile::{is_blocked, Map};
+use crate::world::World;
 
 use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
 use rand::Rng;
 
+const WAND_MIN_CHARGES: i32 = 3;
+const WAND_MAX_CHARGES: i32 = 6;
+
+const GOLD_CHANCE: u32 = 3; // one in three rooms
+const GOLD_PER_LEVEL: i32 = 10;
+
 struct Transition {
     level: u32,
     value: u32,
 }
 
+/// what a kind of monster is like when it spawns
+struct MonsterDef {
+    name: &'static str,
+    ch: char,
+    color: Color,
+    is_bold: bool,
+    fighter: Fighter,
+    /// items it carries and wields from the start
+    equipment: &'static [Item],
+}
+
+const ORC: MonsterDef = MonsterDef {
+    name: "orc",
+    ch: 'o',
+    color: Color::Hostile,
+    is_bold: false,
+    fighter: Fighter {
+        base_max_hp: 10,
+        hp: 10,
+        base_defence: 0,
+        xp: 35,
+        base_power: 3,
+    },
+    equipment: &[],
+};
+
+const TROLL: MonsterDef = MonsterDef {
+    name: "troll",
+    ch: 'T',
+    color: Color::Dangerous,
+    is_bold: false,
+    fighter: Fighter {
+        base_max_hp: 16,
+        hp: 16,
+        base_defence: 1,
+        xp: 100,
+        base_power: 4,
+    },
+    equipment: &[],
+};
+
+const ORC_CAPTAIN: MonsterDef = MonsterDef {
+    name: "orc captain",
+    ch: 'o',
+    color: Color::Dangerous,
+    is_bold: true,
+    fighter: Fighter {
+        base_max_hp: 14,
+        hp: 14,
+        base_defence: 0,
+        xp: 60,
+        base_power: 3,
+    },
+    equipment: &[Item::Shield],
+};
+
 fn from_dungeon_level(table: &[Transition], level: u32) -> u32 {
     table
         .iter()
@@ -20,7 +86,7 @@ fn from_dungeon_level(table: &[Transition], level: u32) -> u32 {
         .map_or(0, |transition| transition.value)
 }
 
-pub fn spawn(room: Rect, objects: &mut Vec<Object>, map: &Map, level: u32) {
+pub fn spawn(room: Rect, world: &mut World, map: &Map, level: u32) {
     // maximum number of monsters per room
     let max_monsters = from_dungeon_level(
         &[
@@ -37,7 +103,7 @@ pub fn spawn(room: Rect, objects: &mut Vec<Object>, map: &Map, level: u32) {
     );
 
     // choose random number of monsters
-    let num_monsters = rand::thread_rng().gen_range(0, max_monsters + 1);
+    let num_monsters = rng::get().gen_range(0, max_monsters + 1);
 
     let troll_chance = from_dungeon_level(
         &[
@@ -61,14 +127,32 @@ pub fn spawn(room: Rect, objects: &mut Vec<Object>, map: &Map, level: u32) {
         level,
     );
 
+    let orc_captain_chance = from_dungeon_level(
+        &[
+            Transition {
+                level: 2,
+                value: 10,
+            },
+            Transition {
+                level: 5,
+                value: 20,
+            },
+        ],
+        level,
+    );
+
     let monster_chances = &mut [
         Weighted {
             weight: 80,
-            item: "orc",
+            item: &ORC,
         },
         Weighted {
             weight: troll_chance,
-            item: "troll",
+            item: &TROLL,
+        },
+        Weighted {
+            weight: orc_captain_chance,
+            item: &ORC_CAPTAIN,
         },
     ];
 
@@ -112,147 +196,240 @@ pub fn spawn(room: Rect, objects: &mut Vec<Object>, map: &Map, level: u32) {
             ),
             item: Item::Shield,
         },
+        Weighted {
+            weight: 15,
+            item: Item::Ration,
+        },
+        Weighted {
+            weight: 10,
+            item: Item::Apple,
+        },
+        Weighted {
+            weight: from_dungeon_level(&[Transition { level: 2, value: 8 }], level),
+            item: Item::Striking,
+        },
+        Weighted {
+            weight: from_dungeon_level(&[Transition { level: 2, value: 4 }], level),
+            item: Item::Digging,
+        },
+        Weighted {
+            weight: from_dungeon_level(&[Transition { level: 2, value: 6 }], level),
+            item: Item::Sleep,
+        },
+        Weighted {
+            weight: from_dungeon_level(&[Transition { level: 3, value: 4 }], level),
+            item: Item::TeleportOther,
+        },
+        Weighted {
+            weight: from_dungeon_level(&[Transition { level: 3, value: 5 }], level),
+            item: Item::Recharging,
+        },
     ];
 
     for _ in 0..num_monsters {
         // choose random spot for this monster
-        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
-        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+        let x = rng::get().gen_range(room.x1 + 1, room.x2);
+        let y = rng::get().gen_range(room.y1 + 1, room.y2);
 
-        if !is_blocked(x, y, map, objects) {
+        if !is_blocked(x, y, map, world) {
             let monster_choice = WeightedChoice::new(monster_chances);
-            let mut monster = match monster_choice.ind_sample(&mut rand::thread_rng()) {
-                "orc" => {
-                    let mut orc =
-                        Object::new(x, y, 'o', pancurses::COLOR_GREEN, false, "orc", true);
-                    orc.fighter = Some(Fighter {
-                        base_max_hp: 10,
-                        hp: 10,
-                        base_defence: 0,
-                        xp: 35,
-                        base_power: 3,
-                    });
-                    orc.ai = Some(AI::Basic);
-                    orc
-                }
-                "troll" => {
-                    let mut troll =
-                        Object::new(x, y, 'T', pancurses::COLOR_YELLOW, false, "troll", true);
-                    troll.fighter = Some(Fighter {
-                        base_max_hp: 16,
-                        hp: 16,
-                        base_defence: 1,
-                        xp: 100,
-                        base_power: 4,
-                    });
-                    troll.ai = Some(AI::Basic);
-                    troll
-                }
-                _ => unreachable!(),
-            };
-
-            monster.alive = true;
-
-            objects.push(monster);
+            spawn_monster(world, monster_choice.ind_sample(&mut rng::get()), x, y);
         }
     }
 
-    let num_items = rand::thread_rng().gen_range(0, max_items + 1);
+    let num_items = rng::get().gen_range(0, max_items + 1);
 
     for _ in 0..num_items {
         // choose random spot for this item
-        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
-        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+        let x = rng::get().gen_range(room.x1 + 1, room.x2);
+        let y = rng::get().gen_range(room.y1 + 1, room.y2);
 
         // only place it if the tile is not blocked
-        if !is_blocked(x, y, map, objects) {
-            // create a healing potion
+        if !is_blocked(x, y, map, world) {
             let item_choice = WeightedChoice::new(item_chances);
-            let item = match item_choice.ind_sample(&mut rand::thread_rng()) {
-                Item::Heal => {
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '!',
-                        pancurses::COLOR_MAGENTA,
-                        false,
-                        "healing potion",
-                        false,
-                    );
-                    object.item = Some(Item::Heal);
-                    object
-                }
-                Item::Lightning => {
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '#',
-                        pancurses::COLOR_CYAN,
-                        false,
-                        "scroll of lightning",
-                        false,
-                    );
-                    object.item = Some(Item::Lightning);
-                    object
-                }
-                Item::Fire => {
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '#',
-                        pancurses::COLOR_RED,
-                        false,
-                        "scroll of fire",
-                        false,
-                    );
-                    object.item = Some(Item::Fire);
-                    object
-                }
-
-                Item::Confusion => {
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '#',
-                        pancurses::COLOR_GREEN,
-                        false,
-                        "scroll of confusion",
-                        false,
-                    );
-                    object.item = Some(Item::Confusion);
-                    object
-                }
-
-                Item::Sword => {
-                    let mut object =
-                        Object::new(x, y, '/', pancurses::COLOR_BLUE, false, "sword", false);
-                    object.item = Some(Item::Sword);
-                    object.equipment = Some(Equipment {
-                        equipped: false,
-                        slot: Slot::RightHand,
-                        power_bonus: 3,
-                        defense_bonus: 0,
-                        max_hp_bonus: 0,
-                    });
-                    object
-                }
-
-                Item::Shield => {
-                    let mut object =
-                        Object::new(x, y, '[', pancurses::COLOR_BLUE, false, "shield", false);
-                    object.item = Some(Item::Shield);
-                    object.equipment = Some(Equipment {
-                        equipped: false,
-                        slot: Slot::RightHand,
-                        power_bonus: 0,
-                        defense_bonus: 1,
-                        max_hp_bonus: 10,
-                    });
-                    object
-                }
-            };
-
-            objects.push(item);
+            make_item(world, item_choice.ind_sample(&mut rng::get()), x, y);
+        }
+    }
+
+    if rng::get().gen_range(0, GOLD_CHANCE) == 0 {
+        let x = rng::get().gen_range(room.x1 + 1, room.x2);
+        let y = rng::get().gen_range(room.y1 + 1, room.y2);
+
+        if !is_blocked(x, y, map, world) {
+            // deeper levels hold bigger piles
+            let amount = rng::get().gen_range(1, GOLD_PER_LEVEL * level as i32 + 1);
+            make_gold(world, x, y, amount);
+        }
+    }
+}
+
+pub fn make_gold(world: &mut World, x: i32, y: i32, amount: i32) -> ObjectId {
+    let object = world.spawn_at(
+        x,
+        y,
+        '$',
+        Color::Gold,
+        true,
+        &format!("{} gold pieces", amount),
+        false,
+    );
+    world.gold.insert(object, amount);
+    object
+}
+
+/// create an item object of the given kind at the given position
+pub fn make_item(world: &mut World, item: Item, x: i32, y: i32) -> ObjectId {
+    match item {
+        Item::Heal => {
+            let object = world.spawn_at(x, y, '!', Color::Potion, false, "healing potion", false);
+            world.items.insert(object, Item::Heal);
+            object
+        }
+        Item::Lightning => {
+            let object = world.spawn_at(
+                x,
+                y,
+                '#',
+                Color::Scroll,
+                false,
+                "scroll of lightning",
+                false,
+            );
+            world.items.insert(object, Item::Lightning);
+            object
+        }
+        Item::Fire => {
+            let object = world.spawn_at(x, y, '#', Color::Scroll, false, "scroll of fire", false);
+            world.items.insert(object, Item::Fire);
+            object
+        }
+
+        Item::Confusion => {
+            let object = world.spawn_at(
+                x,
+                y,
+                '#',
+                Color::Scroll,
+                false,
+                "scroll of confusion",
+                false,
+            );
+            world.items.insert(object, Item::Confusion);
+            object
+        }
+
+        Item::Sword => {
+            let object = world.spawn_at(x, y, '/', Color::Weapon, false, "sword", false);
+            world.items.insert(object, Item::Sword);
+            world.equipment.insert(
+                object,
+                Equipment {
+                    equipped: false,
+                    slot: Slot::RightHand,
+                    power_bonus: 3,
+                    defense_bonus: 0,
+                    max_hp_bonus: 0,
+                },
+            );
+            object
+        }
+
+        Item::Shield => {
+            let object = world.spawn_at(x, y, '[', Color::Armour, false, "shield", false);
+            world.items.insert(object, Item::Shield);
+            world.equipment.insert(
+                object,
+                Equipment {
+                    equipped: false,
+                    slot: Slot::RightHand,
+                    power_bonus: 0,
+                    defense_bonus: 1,
+                    max_hp_bonus: 10,
+                },
+            );
+            object
+        }
+
+        Item::Striking => make_wand(world, x, y, "wand of striking", item),
+        Item::Digging => make_wand(world, x, y, "wand of digging", item),
+        Item::Sleep => make_wand(world, x, y, "wand of sleep", item),
+        Item::TeleportOther => make_wand(world, x, y, "wand of teleport other", item),
+
+        Item::Recharging => {
+            let object = world.spawn_at(
+                x,
+                y,
+                '#',
+                Color::Scroll,
+                false,
+                "scroll of recharging",
+                false,
+            );
+            world.items.insert(object, Item::Recharging);
+            object
         }
+
+        Item::Ration => {
+            let object = world.spawn_at(x, y, '%', Color::Food, false, "food ration", false);
+            world.items.insert(object, Item::Ration);
+            object
+        }
+
+        Item::Apple => {
+            let object = world.spawn_at(x, y, '%', Color::Food, false, "apple", false);
+            world.items.insert(object, Item::Apple);
+            object
+        }
+
+        // corpses are only ever left behind by dying monsters
+        Item::Corpse => unreachable!(),
+    }
+}
+
+fn make_wand(world: &mut World, x: i32, y: i32, name: &str, item: Item) -> ObjectId {
+    let object = world.spawn_at(x, y, '/', Color::Wand, true, name, false);
+    world.items.insert(object, item);
+    let charges = rng::get().gen_range(WAND_MIN_CHARGES, WAND_MAX_CHARGES + 1);
+    world.charges.insert(object, charges);
+    object
+}
+
+/// a monster of some kind, carrying what that kind starts out with
+fn spawn_monster(world: &mut World, def: &MonsterDef, x: i32, y: i32) -> ObjectId {
+    let monster = world.spawn_at(x, y, def.ch, def.color, def.is_bold, def.name, true);
+    world.fighters.insert(monster, def.fighter);
+    world.ais.insert(monster, AI::Basic);
+    for &item in def.equipment {
+        give_equipped(world, monster, item);
+    }
+    monster
+}
+
+/// put an item in a monster's inventory, equipping it if it can be
+fn give_equipped(world: &mut World, monster: ObjectId, item: Item) {
+    let (x, y) = world.pos(monster);
+    let item = make_item(world, item, x, y);
+    if let Some(equipment) = world.equipment.get_mut(item) {
+        equipment.equipped = true;
+    }
+    world.give(monster, item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object;
+
+    #[test]
+    fn monsters_spawn_with_their_equipment() {
+        let mut world = World::default();
+        let orc = spawn_monster(&mut world, &ORC, 1, 1);
+        let captain = spawn_monster(&mut world, &ORC_CAPTAIN, 2, 1);
+
+        assert!(world.inventory(orc).is_empty());
+        let shield = world.inventory(captain)[0];
+        assert_eq!(world.items.get(shield), Some(&Item::Shield));
+        assert!(world.equipment[shield].equipped);
+        assert_eq!(object::defence(&world, captain), 1);
     }
 }
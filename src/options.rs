@@ -0,0 +1,116 @@
+use crate::paths;
+use crate::theme;
+
+use std::fs;
+use std::io;
+
+const OPTIONS_FILE: &str = "options";
+
+/// Player preferences, kept between runs as `key = value` lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    /// step on gold to pick it up, instead of using the pick up key
+    pub auto_pickup_gold: bool,
+    /// go for the items seen while exploring, and pick them up
+    pub explore_pickup: bool,
+    /// draw walls with line-drawing characters, instead of `#`
+    pub line_walls: bool,
+    /// the name of the color theme
+    pub theme: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            auto_pickup_gold: true,
+            explore_pickup: true,
+            line_walls: true,
+            theme: theme::DEFAULT_THEME.to_string(),
+        }
+    }
+}
+
+impl Options {
+    /// the saved options, with defaults for anything missing or unreadable
+    pub fn load() -> Self {
+        let mut options = Self::default();
+        let contents = fs::read_to_string(paths::data_dir().join(OPTIONS_FILE)).unwrap_or_default();
+        for line in contents.lines() {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            if key == "theme" {
+                options.theme = value.to_string();
+                continue;
+            }
+            let value = match value {
+                "yes" => true,
+                "no" => false,
+                _ => continue,
+            };
+            match key {
+                "auto_pickup_gold" => options.auto_pickup_gold = value,
+                "explore_pickup" => options.explore_pickup = value,
+                "line_walls" => options.line_walls = value,
+                _ => {}
+            }
+        }
+        options
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(paths::data_dir())?;
+        let contents = format!(
+            "auto_pickup_gold = {}\nexplore_pickup = {}\nline_walls = {}\ntheme = {}\n",
+            yes_no(self.auto_pickup_gold),
+            yes_no(self.explore_pickup),
+            yes_no(self.line_walls),
+            self.theme
+        );
+        fs::write(paths::data_dir().join(OPTIONS_FILE), contents)
+    }
+
+    /// what the options screen lists, in order
+    pub fn describe(&self) -> Vec<String> {
+        vec![
+            format!(
+                "Pick up gold automatically: {}",
+                yes_no(self.auto_pickup_gold)
+            ),
+            format!(
+                "Pick up items while exploring: {}",
+                yes_no(self.explore_pickup)
+            ),
+            format!("Draw walls with lines: {}", yes_no(self.line_walls)),
+            format!("Color theme: {}", self.theme),
+        ]
+    }
+
+    /// flip the option at a position of `describe`, or move on to the next
+    /// theme
+    pub fn toggle(&mut self, index: usize) {
+        match index {
+            0 => self.auto_pickup_gold = !self.auto_pickup_gold,
+            1 => self.explore_pickup = !self.explore_pickup,
+            2 => self.line_walls = !self.line_walls,
+            3 => {
+                let themes = theme::available();
+                let next = themes
+                    .iter()
+                    .position(|name| *name == self.theme)
+                    .map_or(0, |index| (index + 1) % themes.len());
+                self.theme = themes[next].clone();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
@@ -0,0 +1,125 @@
+use crate::ai::AI;
+use crate::curses::{Graphics, Menu};
+use crate::game::Game;
+use crate::tile::Map;
+use crate::world::World;
+
+use serde::{Deserialize, Serialize};
+
+const NOTE_MAX_LEN: usize = 40;
+
+/// What the player knows about a level they have been on.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelNotes {
+    pub depth: u32,
+    /// the turn the player arrived on
+    pub arrived: u32,
+    /// what the level looked like when the player left it
+    pub found: Vec<String>,
+    /// whatever the player wrote down
+    pub note: String,
+}
+
+impl LevelNotes {
+    pub fn new(depth: u32, arrived: u32) -> Self {
+        Self {
+            depth,
+            arrived,
+            ..Self::default()
+        }
+    }
+
+    /// one line for the list of levels
+    fn describe(&self, here: bool) -> String {
+        let mut line = format!("{:>3}: arrived on turn {}", self.depth, self.arrived);
+        for found in &self.found {
+            line += &format!(", {}", found);
+        }
+        if here {
+            line += " (you are here)";
+        }
+        if !self.note.is_empty() {
+            line += &format!(" - {}", self.note);
+        }
+        line
+    }
+}
+
+/// what the player has found out about the map so far, for the notes
+pub fn survey(map: &Map, world: &World) -> Vec<String> {
+    let tiles = map.iter().flatten();
+    let (floor, explored) = tiles
+        .filter(|tile| !tile.blocked)
+        .fold((0, 0), |(floor, explored), tile| {
+            (floor + 1, explored + usize::from(tile.visible))
+        });
+    let mut found = vec![format!("{}% explored", explored * 100 / floor.max(1))];
+
+    let seen = |x: i32, y: i32| map[x as usize][y as usize].visible;
+    let stairs = world
+        .positions
+        .iter()
+        .any(|(id, position)| world.stairs.contains(id) && seen(position.x, position.y));
+    if stairs {
+        found.push("stairs found".to_string());
+    }
+    let shop = world.ais.iter().any(|(id, ai)| {
+        let (x, y) = world.pos(id);
+        matches!(ai, AI::Shopkeeper { .. }) && seen(x, y)
+    });
+    if shop {
+        found.push("a shop".to_string());
+    }
+    let items = map
+        .iter()
+        .flatten()
+        .filter(|tile| tile.remembered.is_some_and(|r| r.ch != '>'))
+        .count();
+    if items > 0 {
+        found.push(format!("{} things seen lying around", items));
+    }
+    found
+}
+
+/// show the map of the level and the levels visited, until the player goes back
+pub fn show(game: &mut Game, graphics: &Graphics) {
+    loop {
+        let lines = {
+            let world = game.world.borrow();
+            let found = survey(&game.map, &world);
+            let last = game.levels.len().saturating_sub(1);
+            game.levels
+                .iter()
+                .enumerate()
+                .map(|(i, notes)| {
+                    if i == last {
+                        LevelNotes {
+                            found: found.clone(),
+                            ..notes.clone()
+                        }
+                        .describe(true)
+                    } else {
+                        notes.describe(false)
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let player = {
+            let world = game.world.borrow();
+            world.pos(world.player_id())
+        };
+        let menu = Menu::new("")
+            .keyed('n', "write a note for this level")
+            .keyed('q', "go back")
+            .footer();
+        let picked =
+            graphics.choose_on(&menu, || graphics.draw_overview(&game.map, player, &lines));
+        if picked != Some(0) {
+            break;
+        }
+        let note = graphics.ask_line("What do you want to note about this level?", NOTE_MAX_LEN);
+        if let (Some(note), Some(notes)) = (note, game.levels.last_mut()) {
+            notes.note = note;
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::tile::{self, Map};
+use crate::world::World;
+
+use std::collections::VecDeque;
+
+/// the eight directions a step can go in
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// Whether the player could walk onto a tile as far as they know: it has been
+/// seen, isn't a wall and nothing stands in the way, going by the tiles
+/// `occupied` marks.
+fn is_walkable(x: i32, y: i32, map: &Map, occupied: &[bool]) -> bool {
+    tile::in_bounds(x, y, map)
+        && map[x as usize][y as usize].visible
+        && !map[x as usize][y as usize].blocked
+        && !occupied[(x * tile::height(map) + y) as usize]
+}
+
+/// the tiles something blocking stands on, all looked up in one go rather
+/// than for every tile a search looks at
+fn occupied(map: &Map, world: &World) -> Vec<bool> {
+    let height = tile::height(map);
+    let mut occupied = vec![false; (tile::width(map) * height) as usize];
+    for (id, _) in world.blockers.iter() {
+        if let Some(position) = world.positions.get(id) {
+            if tile::in_bounds(position.x, position.y, map) {
+                occupied[(position.x * height + position.y) as usize] = true;
+            }
+        }
+    }
+    occupied
+}
+
+/// The first step of a shortest walk from the player to the nearest tile
+/// `is_goal` picks out, over tiles the player has seen. None if no such tile
+/// can be reached, or if the player is on the only one.
+pub fn first_step(
+    map: &Map,
+    world: &World,
+    is_goal: impl Fn(i32, i32) -> bool,
+) -> Option<(i32, i32)> {
+    let (start_x, start_y) = world.pos(world.player_id());
+    let (width, height) = (tile::width(map), tile::height(map));
+    // the step each tile was first reached by
+    let mut first = vec![None; (width * height) as usize];
+    let occupied = occupied(map, world);
+    let mut queue = VecDeque::new();
+
+    first[(start_x * height + start_y) as usize] = Some((0, 0));
+    queue.push_back((start_x, start_y));
+    while let Some((x, y)) = queue.pop_front() {
+        for (dx, dy) in DIRECTIONS {
+            let (next_x, next_y) = (x + dx, y + dy);
+            if !is_walkable(next_x, next_y, map, &occupied) {
+                continue;
+            }
+            let index = (next_x * height + next_y) as usize;
+            if first[index].is_some() {
+                continue;
+            }
+            let step = match first[(x * height + y) as usize] {
+                Some((0, 0)) | None => (dx, dy),
+                Some(step) => step,
+            };
+            if is_goal(next_x, next_y) {
+                return Some(step);
+            }
+            first[index] = Some(step);
+            queue.push_back((next_x, next_y));
+        }
+    }
+    None
+}
+
+/// whether a known tile borders on tiles that haven't been seen yet
+pub fn is_frontier(x: i32, y: i32, map: &Map) -> bool {
+    DIRECTIONS.iter().any(|(dx, dy)| {
+        let (x, y) = (x + dx, y + dy);
+        tile::in_bounds(x, y, map) && !map[x as usize][y as usize].visible
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Color;
+    use crate::tile::Tile;
+
+    /// a seen, empty map with the player standing at (x, y)
+    fn setup(width: usize, height: usize, x: i32, y: i32) -> (Map, World) {
+        let seen = Tile {
+            visible: true,
+            ..Tile::empty()
+        };
+        let map = vec![vec![seen; height]; width];
+        let mut world = World::default();
+        let player = world.spawn_at(x, y, '@', Color::Player, false, "player", true);
+        world.set_player(player);
+        (map, world)
+    }
+
+    #[test]
+    fn steps_straight_towards_the_goal() {
+        let (map, world) = setup(5, 5, 0, 2);
+        assert_eq!(
+            first_step(&map, &world, |x, y| (x, y) == (4, 2)),
+            Some((1, 0))
+        );
+        assert_eq!(
+            first_step(&map, &world, |x, y| (x, y) == (2, 4)),
+            Some((1, 1))
+        );
+    }
+
+    #[test]
+    fn walks_around_walls() {
+        let (mut map, world) = setup(5, 5, 0, 0);
+        // a wall down the middle with a gap at the bottom
+        for tile in &mut map[2][..4] {
+            *tile = Tile {
+                visible: true,
+                ..Tile::wall()
+            };
+        }
+        // heading straight for the goal is a longer way round than going down first
+        let step = first_step(&map, &world, |x, y| (x, y) == (4, 0));
+        assert!(matches!(step, Some((0, 1)) | Some((1, 1))), "{:?}", step);
+    }
+
+    #[test]
+    fn goes_to_the_nearest_goal() {
+        let (map, world) = setup(7, 1, 3, 0);
+        assert_eq!(
+            first_step(&map, &world, |x, _| x == 0 || x == 5),
+            Some((1, 0))
+        );
+    }
+
+    #[test]
+    fn no_step_without_a_known_way() {
+        let (mut map, mut world) = setup(5, 1, 0, 0);
+        map[2][0].visible = false;
+        assert_eq!(first_step(&map, &world, |x, _| x == 4), None);
+
+        map[2][0].visible = true;
+        world.spawn_at(2, 0, 'o', Color::Player, false, "orc", true);
+        assert_eq!(first_step(&map, &world, |x, _| x == 4), None);
+    }
+
+    #[test]
+    fn no_step_when_standing_on_the_goal() {
+        let (map, world) = setup(3, 3, 1, 1);
+        assert_eq!(first_step(&map, &world, |x, y| (x, y) == (1, 1)), None);
+    }
+}
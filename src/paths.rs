@@ -0,0 +1,34 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "roguelike-rs";
+
+/// where persistent game data (scores, dumps, saves) lives, following the XDG spec
+pub fn data_dir() -> PathBuf {
+    let base = match (env::var_os("XDG_DATA_HOME"), env::var_os("HOME")) {
+        (Some(data), _) => PathBuf::from(data),
+        (None, Some(home)) => PathBuf::from(home).join(".local").join("share"),
+        (None, None) => PathBuf::from("."),
+    };
+    base.join(APP_DIR)
+}
+
+/// where the player's configuration (the keymap) lives, following the XDG spec
+pub fn config_dir() -> PathBuf {
+    let base = match (env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME")) {
+        (Some(config), _) => PathBuf::from(config),
+        (None, Some(home)) => PathBuf::from(home).join(".config"),
+        (None, None) => PathBuf::from("."),
+    };
+    base.join(APP_DIR)
+}
+
+/// write a temporary file and move it over, so a crash never leaves half a
+/// file and readers never see one
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
@@ -0,0 +1,38 @@
+use rand::{Rng, SeedableRng, StdRng};
+use std::cell::RefCell;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_seed(&[0]));
+}
+
+/// Handle to the game's random number generator. Every random choice goes
+/// through it, so a run can be reproduced from its seed.
+pub struct GameRng;
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+}
+
+pub fn get() -> GameRng {
+    GameRng
+}
+
+/// restart the generator from a seed
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::from_seed(&[seed as usize]));
+}
+
+/// a fresh seed for a new run
+pub fn random_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    nanos ^ (std::process::id() as u64).rotate_left(32)
+}
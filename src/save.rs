@@ -0,0 +1,145 @@
+use crate::game::Game;
+use crate::overview::LevelNotes;
+use crate::paths;
+use crate::tile::Map;
+use crate::world::World;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const SAVE_FILE: &str = "save.json";
+// bump whenever the saved structures change, old saves are refused rather than misread
+const SAVE_VERSION: u32 = 9;
+
+/// Everything needed to pick a run back up where it was left.
+#[derive(Serialize, Deserialize)]
+struct Save {
+    version: u32,
+    map: Map,
+    world: World,
+    log: Vec<String>,
+    dungeon_level: u32,
+    turns: u32,
+    kills: HashMap<String, u32>,
+    levels: Vec<LevelNotes>,
+    seed: u64,
+}
+
+impl Save {
+    /// the run as it is now
+    fn of(game: &Game) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            map: game.map.clone(),
+            world: game.world.borrow().clone(),
+            log: game.log.clone(),
+            dungeon_level: game.dungeon_level,
+            turns: game.turns,
+            kills: game.kills.clone(),
+            levels: game.levels.clone(),
+            seed: game.seed,
+        }
+    }
+
+    /// put the saved run into the game, unless another version of the game made it
+    fn restore(self, game: &mut Game) -> io::Result<()> {
+        if self.version != SAVE_VERSION {
+            return Err(io::Error::other(
+                "the save was made by another version of the game",
+            ));
+        }
+
+        game.map = self.map;
+        *game.world.borrow_mut() = self.world;
+        game.log = self.log;
+        game.statuses.clear();
+        game.dungeon_level = self.dungeon_level;
+        game.turns = self.turns;
+        game.kills = self.kills;
+        game.levels = self.levels;
+        game.seed = self.seed;
+        Ok(())
+    }
+}
+
+fn path() -> PathBuf {
+    paths::data_dir().join(SAVE_FILE)
+}
+
+pub fn exists() -> bool {
+    path().is_file()
+}
+
+/// store the run, replacing any older save
+pub fn write(game: &Game) -> io::Result<()> {
+    let contents = serde_json::to_string(&Save::of(game)).map_err(io::Error::other)?;
+
+    fs::create_dir_all(paths::data_dir())?;
+    paths::write_atomic(&path(), &contents)
+}
+
+/// put the saved run into the game and delete the save, so a run can only be continued once
+pub fn load(game: &mut Game) -> io::Result<()> {
+    let contents = fs::read_to_string(path())?;
+    let save: Save = serde_json::from_str(&contents).map_err(io::Error::other)?;
+    save.restore(game)?;
+    delete()
+}
+
+pub fn delete() -> io::Result<()> {
+    match fs::remove_file(path()) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(game: &Game) -> io::Result<Game> {
+        let contents = serde_json::to_string(&Save::of(game)).map_err(io::Error::other)?;
+        let save: Save = serde_json::from_str(&contents).map_err(io::Error::other)?;
+        let mut loaded = Game::default();
+        save.restore(&mut loaded)?;
+        Ok(loaded)
+    }
+
+    #[test]
+    fn saved_runs_load_the_same() {
+        let mut game = Game::default();
+        game.begin("Tester");
+        game.turns = 42;
+        game.log.push("Something happened.".to_string());
+        game.kills.insert("orc".to_string(), 3);
+
+        let loaded = round_trip(&game).unwrap();
+        assert_eq!(loaded.turns, 42);
+        assert_eq!(loaded.log, game.log);
+        assert_eq!(loaded.kills, game.kills);
+        assert_eq!(loaded.levels, game.levels);
+        assert_eq!(loaded.seed, game.seed);
+        let (world, loaded_world) = (game.world.borrow(), loaded.world.borrow());
+        let player_id = loaded_world.player_id();
+        assert_eq!(loaded_world.name(player_id), "Tester");
+        assert_eq!(loaded_world.pos(player_id), world.pos(world.player_id()));
+        assert_eq!(loaded_world.inventory(player_id).len(), 1);
+        // nothing gets lost along the way
+        assert_eq!(
+            serde_json::to_string(&Save::of(&loaded)).unwrap(),
+            serde_json::to_string(&Save::of(&game)).unwrap()
+        );
+    }
+
+    #[test]
+    fn saves_from_other_versions_are_refused() {
+        let mut game = Game::default();
+        game.begin("Tester");
+        let mut save = Save::of(&game);
+        save.version -= 1;
+        assert!(save.restore(&mut Game::default()).is_err());
+    }
+}
@@ -0,0 +1,436 @@
+use crate::ai::AI;
+use crate::curses::Status;
+use crate::game::{Game, PlayerAction};
+use crate::item::Item;
+use crate::map_gen::Rect;
+use crate::object::Fighter;
+use crate::object_gen::make_item;
+use crate::rng;
+use crate::store::ObjectId;
+use crate::theme::Color;
+use crate::tile::Map;
+use crate::world::World;
+use rand::Rng;
+
+const STOCK_CHANCE: u32 = 2; // one in two floor tiles
+const SELL_FACTOR: i32 = 2; // shopkeepers pay half the price
+
+/// what a shop can have on its shelves
+const STOCK: &[Item] = &[
+    Item::Heal,
+    Item::Lightning,
+    Item::Confusion,
+    Item::Fire,
+    Item::Sword,
+    Item::Shield,
+    Item::Striking,
+    Item::Digging,
+    Item::Sleep,
+    Item::TeleportOther,
+    Item::Recharging,
+    Item::Ration,
+    Item::Apple,
+];
+
+/// the price of an item, derived from what it is and what it does
+pub fn price(world: &World, id: ObjectId) -> i32 {
+    let base = match world.items.get(id).copied() {
+        Some(Item::Heal) => 20,
+        Some(Item::Lightning) => 40,
+        Some(Item::Confusion) => 30,
+        Some(Item::Fire) => 40,
+        Some(Item::Sword) => 10,
+        Some(Item::Shield) => 10,
+        Some(Item::Striking) => 50,
+        Some(Item::Digging) => 70,
+        Some(Item::Sleep) => 50,
+        Some(Item::TeleportOther) => 70,
+        Some(Item::Recharging) => 100,
+        Some(Item::Ration) => 15,
+        Some(Item::Apple) => 3,
+        Some(Item::Corpse) => 1,
+        None => 0,
+    };
+    let bonus = world.equipment.get(id).map_or(0, |e| {
+        (e.power_bonus + e.defense_bonus) * 20 + e.max_hp_bonus * 2
+    });
+    let charges = world.charges.get(id).map_or(0, |c| c * 10);
+    base + bonus + charges
+}
+
+/// turn one of the rooms with a single entrance into a shop
+pub fn place_shop(rooms: &[Rect], map: &Map, world: &mut World) {
+    let shop = rooms
+        .iter()
+        .map(|room| (room, entrances(room, map)))
+        .find(|(_, entrances)| entrances.len() == 1);
+
+    let (room, door) = match shop {
+        Some((room, entrances)) => (*room, entrances[0]),
+        None => return,
+    };
+
+    // the floor tile just inside the door, where the shopkeeper stands guard
+    let post = (
+        door.0.clamp(room.x1, room.x2 - 1),
+        door.1.clamp(room.y1, room.y2 - 1),
+    );
+    // one step along the wall, to let customers through
+    let aside = if door.0 == post.0 {
+        if post.0 + 1 < room.x2 {
+            (post.0 + 1, post.1)
+        } else {
+            (post.0 - 1, post.1)
+        }
+    } else if post.1 + 1 < room.y2 {
+        (post.0, post.1 + 1)
+    } else {
+        (post.0, post.1 - 1)
+    };
+
+    // clear out whatever was spawned here (the player always starts elsewhere)
+    let cleared = world
+        .positions
+        .iter()
+        .filter(|(_, position)| room.contains(position.x, position.y))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    for id in cleared {
+        world.despawn(id);
+    }
+
+    let mut stock = vec![];
+    for x in room.x1..room.x2 {
+        for y in room.y1..room.y2 {
+            if (x, y) == post || (x, y) == aside || rng::get().gen_range(0, STOCK_CHANCE) != 0 {
+                continue;
+            }
+            let kind = STOCK[rng::get().gen_range(0, STOCK.len())];
+            stock.push(make_item(world, kind, x, y));
+        }
+    }
+
+    let shopkeeper = world.spawn_at(
+        aside.0,
+        aside.1,
+        '@',
+        Color::Peaceful,
+        true,
+        "shopkeeper",
+        true,
+    );
+    world.fighters.insert(
+        shopkeeper,
+        Fighter {
+            base_max_hp: 60,
+            hp: 60,
+            base_defence: 3,
+            xp: 500,
+            base_power: 9,
+        },
+    );
+    world.ais.insert(
+        shopkeeper,
+        AI::Shopkeeper {
+            shop: room,
+            post,
+            aside,
+        },
+    );
+    for item in stock {
+        world.for_sale.insert(item, shopkeeper);
+    }
+}
+
+/// floor tiles right outside the walls of a room
+fn entrances(room: &Rect, map: &Map) -> Vec<(i32, i32)> {
+    let mut entrances = vec![];
+    for x in room.x1..room.x2 {
+        entrances.push((x, room.y1 - 1));
+        entrances.push((x, room.y2));
+    }
+    for y in room.y1..room.y2 {
+        entrances.push((room.x1 - 1, y));
+        entrances.push((room.x2, y));
+    }
+    entrances
+        .into_iter()
+        .filter(|&(x, y)| !map[x as usize][y as usize].blocked)
+        .collect()
+}
+
+/// the player is leaving a shop without paying, make its keeper hostile
+pub fn anger_shopkeeper(shopkeeper_id: ObjectId, game: &mut Game) {
+    let mut world = game.world.borrow_mut();
+    game.statuses.push(Status::new(
+        format!("The {} gets angry!", world.name(shopkeeper_id)),
+        1,
+    ));
+    world.ais.insert(shopkeeper_id, AI::Basic);
+
+    // whatever wasn't paid for is stolen now
+    let player_id = world.player_id();
+    for item in world.inventory(player_id).to_vec() {
+        if world.for_sale.get(item) == Some(&shopkeeper_id) {
+            world.for_sale.remove(item);
+        }
+    }
+}
+
+/// Unpaid items that get used up or destroyed have to be paid for on the
+/// spot. A customer who can't pay angers the shopkeeper.
+pub fn charge_for(item: ObjectId, game: &mut Game) {
+    let (shopkeeper_id, cost, gold) = {
+        let world = game.world.borrow();
+        let shopkeeper_id = match world.for_sale.get(item) {
+            Some(&shopkeeper_id) => shopkeeper_id,
+            None => return,
+        };
+        let gold = world.gold.get(world.player_id()).copied().unwrap_or(0);
+        (shopkeeper_id, price(&world, item), gold)
+    };
+    if gold < cost {
+        anger_shopkeeper(shopkeeper_id, game);
+        return;
+    }
+
+    let mut world = game.world.borrow_mut();
+    let player_id = world.player_id();
+    world.gold.insert(player_id, gold - cost);
+    world.for_sale.remove(item);
+    game.statuses.push(Status::new(
+        format!("\"That one is yours now, for {} gold.\"", cost),
+        1,
+    ));
+}
+
+/// Walking into a peaceful shopkeeper. The trading itself is done with the
+/// buy and sell commands.
+pub fn greet(shopkeeper_id: ObjectId, game: &mut Game) -> PlayerAction {
+    let msg = match shopkeeper_serving(&game.world.borrow()) {
+        Some(id) if id == shopkeeper_id => "\"Welcome! What can I do for you?\"",
+        _ => "\"Please come inside to trade.\"",
+    };
+    game.add_status(msg.to_string(), 1);
+    PlayerAction::DidntTakeTurn
+}
+
+/// the peaceful shopkeeper of the shop the player is in
+pub fn shopkeeper_serving(world: &World) -> Option<ObjectId> {
+    let (x, y) = world.pos(world.player_id());
+    world
+        .ais
+        .iter()
+        .find(|(_, ai)| matches!(ai, AI::Shopkeeper { shop, .. } if shop.contains(x, y)))
+        .map(|(id, _)| id)
+}
+
+/// what a shopkeeper pays for an item
+pub fn offer(world: &World, item: ObjectId) -> i32 {
+    price(world, item) / SELL_FACTOR
+}
+
+/// pay for the unpaid item at a position of the inventory
+pub fn buy(inv_id: usize, game: &mut Game) -> PlayerAction {
+    let mut world = game.world.borrow_mut();
+    let player_id = world.player_id();
+    let item = match world.inventory(player_id).get(inv_id) {
+        Some(&item) => item,
+        None => {
+            drop(world);
+            game.add_status(
+                format!("You don't have an item at {}.", (inv_id + 97) as u8 as char),
+                1,
+            );
+            return PlayerAction::DidntTakeTurn;
+        }
+    };
+    let shopkeeper_id = match world.for_sale.get(item) {
+        Some(&shopkeeper_id) => shopkeeper_id,
+        None => {
+            game.statuses
+                .push(Status::new("\"You already own that.\"".to_string(), 1));
+            return PlayerAction::DidntTakeTurn;
+        }
+    };
+    if shopkeeper_serving(&world) != Some(shopkeeper_id) {
+        game.statuses
+            .push(Status::new("There is nobody here to pay.".to_string(), 1));
+        return PlayerAction::DidntTakeTurn;
+    }
+
+    let cost = price(&world, item);
+    let gold = world.gold.get(player_id).copied().unwrap_or(0);
+    if gold < cost {
+        game.statuses.push(Status::new(
+            format!("\"That's {} gold, which you don't have.\"", cost),
+            1,
+        ));
+        return PlayerAction::DidntTakeTurn;
+    }
+
+    world.gold.insert(player_id, gold - cost);
+    world.for_sale.remove(item);
+    game.statuses.push(Status::new(
+        format!(
+            "You bought the {} for {} gold. \"Thank you!\"",
+            world.name(item),
+            cost
+        ),
+        1,
+    ));
+    PlayerAction::TookTurn
+}
+
+/// sell the item at a position of the inventory to the shop the player is in
+pub fn sell(inv_id: usize, game: &mut Game) -> PlayerAction {
+    let mut world = game.world.borrow_mut();
+    let player_id = world.player_id();
+    let item = match world.inventory(player_id).get(inv_id) {
+        Some(&item) => item,
+        None => {
+            drop(world);
+            game.add_status(
+                format!("You don't have an item at {}.", (inv_id + 97) as u8 as char),
+                1,
+            );
+            return PlayerAction::DidntTakeTurn;
+        }
+    };
+    let shopkeeper_id = match shopkeeper_serving(&world) {
+        Some(shopkeeper_id) => shopkeeper_id,
+        None => {
+            game.statuses.push(Status::new(
+                "There is nobody here to sell to.".to_string(),
+                1,
+            ));
+            return PlayerAction::DidntTakeTurn;
+        }
+    };
+    if world.for_sale.contains(item) {
+        game.statuses
+            .push(Status::new("\"That's mine already!\"".to_string(), 1));
+        return PlayerAction::DidntTakeTurn;
+    }
+
+    let offer = offer(&world, item);
+    *world.gold.get_mut(player_id).unwrap() += offer;
+    game.statuses.push(Status::new(
+        format!("You sold the {} for {} gold.", world.name(item), offer),
+        1,
+    ));
+
+    // it goes on the shelf, right where the player stands
+    let item = world.drop_item(player_id, inv_id);
+    world.for_sale.insert(item, shopkeeper_id);
+    PlayerAction::TookTurn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::Tile;
+
+    /// the player inside a shop, with some gold and its keeper nearby
+    fn shop() -> (Game, ObjectId) {
+        let game = Game {
+            map: vec![vec![Tile::empty(); 10]; 20],
+            ..Game::default()
+        };
+        let shopkeeper = {
+            let mut world = game.world.borrow_mut();
+            let player = world.spawn_at(5, 5, '@', Color::Player, true, "Tester", true);
+            world.set_player(player);
+            world.gold.insert(player, 50);
+            world.inventories.insert(player, vec![]);
+            let shopkeeper = world.spawn_at(4, 4, '@', Color::Peaceful, true, "shopkeeper", true);
+            world.ais.insert(
+                shopkeeper,
+                AI::Shopkeeper {
+                    shop: Rect::new(3, 3, 5, 5),
+                    post: (4, 3),
+                    aside: (4, 4),
+                },
+            );
+            shopkeeper
+        };
+        (game, shopkeeper)
+    }
+
+    /// an unpaid item from the shelves, already picked up
+    fn unpaid(game: &Game, shopkeeper: ObjectId, item: Item) -> ObjectId {
+        let mut world = game.world.borrow_mut();
+        let id = make_item(&mut world, item, 5, 5);
+        world.for_sale.insert(id, shopkeeper);
+        let player_id = world.player_id();
+        world.give(player_id, id);
+        id
+    }
+
+    fn gold(game: &Game) -> i32 {
+        let world = game.world.borrow();
+        world.gold[world.player_id()]
+    }
+
+    #[test]
+    fn prices_count_bonuses_and_charges() {
+        let mut world = World::default();
+        let potion = make_item(&mut world, Item::Heal, 0, 0);
+        let sword = make_item(&mut world, Item::Sword, 0, 0);
+        let wand = make_item(&mut world, Item::Striking, 0, 0);
+        world.charges.insert(wand, 4);
+
+        assert_eq!(price(&world, potion), 20);
+        assert_eq!(price(&world, sword), 10 + 3 * 20);
+        assert_eq!(price(&world, wand), 50 + 4 * 10);
+        assert_eq!(offer(&world, wand), 45);
+    }
+
+    #[test]
+    fn used_up_items_are_charged_for() {
+        let (mut game, shopkeeper) = shop();
+        let potion = unpaid(&game, shopkeeper, Item::Heal);
+        charge_for(potion, &mut game);
+
+        assert_eq!(gold(&game), 30);
+        assert!(!game.world.borrow().for_sale.contains(potion));
+        assert!(matches!(
+            game.world.borrow().ais.get(shopkeeper),
+            Some(AI::Shopkeeper { .. })
+        ));
+    }
+
+    #[test]
+    fn not_paying_angers_the_shopkeeper() {
+        let (mut game, shopkeeper) = shop();
+        let wand = unpaid(&game, shopkeeper, Item::Recharging);
+        charge_for(wand, &mut game);
+
+        assert_eq!(gold(&game), 50);
+        assert!(matches!(
+            game.world.borrow().ais.get(shopkeeper),
+            Some(AI::Basic)
+        ));
+        // what was taken is stolen now
+        assert!(!game.world.borrow().for_sale.contains(wand));
+    }
+
+    #[test]
+    fn sold_items_go_on_the_shelf() {
+        let (mut game, shopkeeper) = shop();
+        let potion = {
+            let mut world = game.world.borrow_mut();
+            let potion = make_item(&mut world, Item::Heal, 5, 5);
+            let player_id = world.player_id();
+            world.give(player_id, potion);
+            potion
+        };
+        assert_eq!(sell(0, &mut game), PlayerAction::TookTurn);
+
+        assert_eq!(gold(&game), 60);
+        let world = game.world.borrow();
+        assert_eq!(world.for_sale.get(potion), Some(&shopkeeper));
+        assert_eq!(world.pos(potion), (5, 5));
+        assert!(world.inventory(world.player_id()).is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Index, IndexMut};
+
+/// Names an object in the world. It stays valid for as long as the object is
+/// there, and never points at another object once that one is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ObjectId {
+    index: usize,
+    generation: u32,
+}
+
+impl ObjectId {
+    fn new(index: usize, generation: u32) -> Self {
+        Self { index, generation }
+    }
+}
+
+/// Hands out object ids. Ids of removed objects are reused with a new
+/// generation, so old ones just stop resolving instead of picking up whatever
+/// took their place.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Entities {
+    generations: Vec<u32>,
+    live: Vec<bool>,
+    free: Vec<usize>,
+}
+
+impl Entities {
+    pub fn create(&mut self) -> ObjectId {
+        match self.free.pop() {
+            Some(index) => {
+                self.generations[index] = self.generations[index].wrapping_add(1);
+                self.live[index] = true;
+                ObjectId::new(index, self.generations[index])
+            }
+            None => {
+                self.generations.push(0);
+                self.live.push(true);
+                ObjectId::new(self.generations.len() - 1, 0)
+            }
+        }
+    }
+
+    /// returns false if the id was already gone
+    pub fn destroy(&mut self, id: ObjectId) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+        self.live[id.index] = false;
+        self.free.push(id.index);
+        true
+    }
+
+    pub fn contains(&self, id: ObjectId) -> bool {
+        self.live.get(id.index) == Some(&true) && self.generations[id.index] == id.generation
+    }
+}
+
+/// One kind of component, for the objects that have it. Iterating goes in id
+/// order, so it is the same every time a level is played from the same seed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Storage<T> {
+    slots: Vec<Option<(u32, T)>>,
+    /// the generation of the last object retired at each index; ids up to it
+    /// belong to objects that are gone
+    #[serde(default)]
+    retired: Vec<Option<u32>>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self {
+            slots: vec![],
+            retired: vec![],
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    /// Give an object this component, replacing the one it had. Returns false
+    /// and leaves the storage alone if the object has been retired, so a stale
+    /// id can't overwrite whatever took its place.
+    pub fn insert(&mut self, id: ObjectId, component: T) -> bool {
+        if self.is_retired(id) {
+            return false;
+        }
+        if self.slots.len() <= id.index {
+            self.slots.resize_with(id.index + 1, || None);
+        }
+        self.slots[id.index] = Some((id.generation, component));
+        true
+    }
+
+    /// remove the component of an object that is leaving the world for good;
+    /// its id can't be given this component again
+    pub fn retire(&mut self, id: ObjectId) -> Option<T> {
+        if self.retired.len() <= id.index {
+            self.retired.resize(id.index + 1, None);
+        }
+        let retired = &mut self.retired[id.index];
+        *retired = Some(retired.map_or(id.generation, |g| g.max(id.generation)));
+        self.remove(id)
+    }
+
+    fn is_retired(&self, id: ObjectId) -> bool {
+        self.retired
+            .get(id.index)
+            .copied()
+            .flatten()
+            .is_some_and(|generation| id.generation <= generation)
+    }
+
+    pub fn remove(&mut self, id: ObjectId) -> Option<T> {
+        let slot = self.slots.get_mut(id.index)?;
+        if slot.as_ref()?.0 != id.generation {
+            return None;
+        }
+        slot.take().map(|(_, component)| component)
+    }
+
+    pub fn get(&self, id: ObjectId) -> Option<&T> {
+        match self.slots.get(id.index)? {
+            Some((generation, component)) if *generation == id.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut T> {
+        match self.slots.get_mut(id.index)? {
+            Some((generation, component)) if *generation == id.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, id: ObjectId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ObjectId, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.as_ref()
+                .map(|(generation, component)| (ObjectId::new(index, *generation), component))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ObjectId, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                slot.as_mut()
+                    .map(|(generation, component)| (ObjectId::new(index, *generation), component))
+            })
+    }
+
+    /// the objects that have this component, to go through while changing them
+    pub fn ids(&self) -> Vec<ObjectId> {
+        self.iter().map(|(id, _)| id).collect()
+    }
+}
+
+impl<T> Index<ObjectId> for Storage<T> {
+    type Output = T;
+
+    fn index(&self, id: ObjectId) -> &T {
+        self.get(id).expect("object doesn't have this component")
+    }
+}
+
+impl<T> IndexMut<ObjectId> for Storage<T> {
+    fn index_mut(&mut self, id: ObjectId) -> &mut T {
+        self.get_mut(id)
+            .expect("object doesn't have this component")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_index_gets_a_new_generation() {
+        let mut entities = Entities::default();
+        let first = entities.create();
+        assert!(entities.destroy(first));
+        let second = entities.create();
+
+        assert_eq!(first.index, second.index);
+        assert_ne!(first.generation, second.generation);
+        assert!(!entities.contains(first));
+        assert!(entities.contains(second));
+    }
+
+    #[test]
+    fn destroying_twice_is_refused() {
+        let mut entities = Entities::default();
+        let id = entities.create();
+        assert!(entities.destroy(id));
+        assert!(!entities.destroy(id));
+        // and the index is only handed out once
+        let (a, b) = (entities.create(), entities.create());
+        assert_ne!(a.index, b.index);
+    }
+
+    #[test]
+    fn stale_ids_do_not_resolve() {
+        let mut entities = Entities::default();
+        let mut names = Storage::default();
+        let old = entities.create();
+        names.insert(old, "old");
+        entities.destroy(old);
+        names.remove(old);
+        let new = entities.create();
+        names.insert(new, "new");
+
+        assert_eq!(names.get(old), None);
+        assert_eq!(names.remove(old), None);
+        assert_eq!(names.get(new), Some(&"new"));
+        assert_eq!(names.ids(), vec![new]);
+    }
+
+    #[test]
+    fn retired_ids_cannot_insert() {
+        let mut entities = Entities::default();
+        let mut names = Storage::default();
+        let old = entities.create();
+        names.insert(old, "old");
+        assert_eq!(names.retire(old), Some("old"));
+        entities.destroy(old);
+        let new = entities.create();
+
+        assert!(!names.insert(old, "stale"));
+        assert!(!names.contains(old));
+        assert!(names.insert(new, "new"));
+        assert_eq!(names.get(new), Some(&"new"));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::paths;
+
+use pancurses::{chtype, A_BOLD, A_DIM, A_NORMAL, A_REVERSE, A_UNDERLINE};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const THEMES_DIR: &str = "themes";
+
+pub const DEFAULT_THEME: &str = "default";
+/// attributes only, for terminals without colors or players who prefer it
+pub const MONOCHROME_THEME: &str = "monochrome";
+
+/// What something on the screen is, rather than how it looks. The theme
+/// decides the look.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Color {
+    Wall,
+    Floor,
+    /// tiles and objects seen before but out of view now
+    Remembered,
+    Player,
+    Hostile,
+    /// monsters to be careful with
+    Dangerous,
+    Peaceful,
+    Corpse,
+    Stairs,
+    Gold,
+    Potion,
+    Scroll,
+    Wand,
+    Weapon,
+    Armour,
+    Food,
+    /// messages, from ordinary to alarming
+    Message,
+    Info,
+    Good,
+    Warning,
+    Danger,
+}
+
+/// every color with its name in theme files, in the order of `Color`
+const COLORS: &[(&str, Color)] = &[
+    ("wall", Color::Wall),
+    ("floor", Color::Floor),
+    ("remembered", Color::Remembered),
+    ("player", Color::Player),
+    ("hostile", Color::Hostile),
+    ("dangerous", Color::Dangerous),
+    ("peaceful", Color::Peaceful),
+    ("corpse", Color::Corpse),
+    ("stairs", Color::Stairs),
+    ("gold", Color::Gold),
+    ("potion", Color::Potion),
+    ("scroll", Color::Scroll),
+    ("wand", Color::Wand),
+    ("weapon", Color::Weapon),
+    ("armour", Color::Armour),
+    ("food", Color::Food),
+    ("message", Color::Message),
+    ("info", Color::Info),
+    ("good", Color::Good),
+    ("warning", Color::Warning),
+    ("danger", Color::Danger),
+];
+
+/// the colors every terminal with colors has
+const BASIC_COLORS: &[(&str, i16)] = &[
+    ("black", pancurses::COLOR_BLACK),
+    ("red", pancurses::COLOR_RED),
+    ("green", pancurses::COLOR_GREEN),
+    ("yellow", pancurses::COLOR_YELLOW),
+    ("blue", pancurses::COLOR_BLUE),
+    ("magenta", pancurses::COLOR_MAGENTA),
+    ("cyan", pancurses::COLOR_CYAN),
+    ("white", pancurses::COLOR_WHITE),
+];
+
+/// How one color is drawn: a basic color, optionally a finer one for
+/// terminals with 256 colors, and attributes such as bold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Style {
+    basic: i16,
+    extended: Option<i16>,
+    attributes: chtype,
+}
+
+impl Style {
+    const fn new(basic: i16, extended: i16, attributes: chtype) -> Self {
+        Self {
+            basic,
+            extended: Some(extended),
+            attributes,
+        }
+    }
+}
+
+/// how the built-in default theme draws a color
+fn default_style(color: Color) -> Style {
+    use pancurses::{
+        COLOR_BLUE, COLOR_CYAN, COLOR_GREEN, COLOR_MAGENTA, COLOR_RED, COLOR_WHITE, COLOR_YELLOW,
+    };
+    match color {
+        Color::Wall => Style::new(COLOR_WHITE, 250, A_BOLD),
+        Color::Floor => Style::new(COLOR_WHITE, 244, A_BOLD),
+        Color::Remembered => Style::new(COLOR_WHITE, 239, A_DIM),
+        Color::Player => Style::new(COLOR_WHITE, 231, A_NORMAL),
+        Color::Hostile => Style::new(COLOR_GREEN, 71, A_NORMAL),
+        Color::Dangerous => Style::new(COLOR_RED, 167, A_NORMAL),
+        Color::Peaceful => Style::new(COLOR_YELLOW, 180, A_NORMAL),
+        Color::Corpse => Style::new(COLOR_RED, 124, A_NORMAL),
+        Color::Stairs => Style::new(COLOR_RED, 203, A_NORMAL),
+        Color::Gold => Style::new(COLOR_YELLOW, 220, A_NORMAL),
+        Color::Potion => Style::new(COLOR_MAGENTA, 170, A_NORMAL),
+        Color::Scroll => Style::new(COLOR_CYAN, 117, A_NORMAL),
+        Color::Wand => Style::new(COLOR_YELLOW, 137, A_NORMAL),
+        Color::Weapon => Style::new(COLOR_BLUE, 75, A_NORMAL),
+        Color::Armour => Style::new(COLOR_CYAN, 67, A_NORMAL),
+        Color::Food => Style::new(COLOR_GREEN, 143, A_NORMAL),
+        Color::Message => Style::new(COLOR_WHITE, 252, A_NORMAL),
+        Color::Info => Style::new(COLOR_BLUE, 111, A_NORMAL),
+        Color::Good => Style::new(COLOR_GREEN, 114, A_NORMAL),
+        Color::Warning => Style::new(COLOR_YELLOW, 214, A_NORMAL),
+        Color::Danger => Style::new(COLOR_RED, 196, A_NORMAL),
+    }
+}
+
+/// how a color looks without colors, where only attributes tell things apart
+fn monochrome_attributes(color: Color) -> chtype {
+    match color {
+        Color::Wall | Color::Floor | Color::Player | Color::Stairs => A_BOLD,
+        Color::Hostile | Color::Dangerous | Color::Danger => A_BOLD,
+        Color::Warning => A_UNDERLINE,
+        Color::Remembered => A_DIM,
+        _ => A_NORMAL,
+    }
+}
+
+/// How the semantic colors are drawn, either a built-in theme or one read
+/// from the themes directory in the config directory.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    /// in the order of `Color`
+    styles: Vec<Style>,
+    /// drawn with attributes alone
+    monochrome: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_THEME.to_string(),
+            styles: COLORS
+                .iter()
+                .map(|&(_, color)| default_style(color))
+                .collect(),
+            monochrome: false,
+        }
+    }
+}
+
+impl Theme {
+    /// the theme with a name, the default one if there is no such theme
+    pub fn load(name: &str) -> Self {
+        match name {
+            DEFAULT_THEME => Self::default(),
+            MONOCHROME_THEME => Self {
+                name: name.to_string(),
+                monochrome: true,
+                ..Self::default()
+            },
+            _ => match fs::read_to_string(themes_dir().join(name)) {
+                Ok(contents) => Self::parse(name, &contents),
+                Err(_) => Self::default(),
+            },
+        }
+    }
+
+    /// `color = basic [0-255] [attributes]` lines change the look of one
+    /// color of the default theme, e.g. `hostile = green 70 bold`; lines that
+    /// can't be read are skipped
+    fn parse(name: &str, contents: &str) -> Self {
+        let mut theme = Self {
+            name: name.to_string(),
+            ..Self::default()
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            let index = match COLORS.iter().position(|(color, _)| *color == key) {
+                Some(index) => index,
+                None => continue,
+            };
+            if let Some(style) = parse_style(value) {
+                theme.styles[index] = style;
+            }
+        }
+        theme
+    }
+
+    /// Set up the color pairs of the terminal for the theme. Terminals with
+    /// 256 colors get the finer colors, those without colors get by on
+    /// attributes.
+    pub fn apply(&mut self) {
+        if !pancurses::has_colors() {
+            self.monochrome = true;
+        }
+        if self.monochrome {
+            return;
+        }
+        let extended = pancurses::COLORS() >= 256;
+        for (index, style) in self.styles.iter().enumerate() {
+            let foreground = match style.extended {
+                Some(color) if extended => color,
+                _ => style.basic,
+            };
+            pancurses::init_pair(pair(index), foreground, pancurses::COLOR_BLACK);
+        }
+    }
+
+    /// what to turn on to draw in a color
+    pub fn attributes(&self, color: Color) -> chtype {
+        if self.monochrome {
+            monochrome_attributes(color)
+        } else {
+            let index = color as usize;
+            pancurses::COLOR_PAIR(pair(index) as chtype) | self.styles[index].attributes
+        }
+    }
+}
+
+/// the names of the themes that can be picked, built-in ones first
+pub fn available() -> Vec<String> {
+    let mut files = fs::read_dir(themes_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name != DEFAULT_THEME && name != MONOCHROME_THEME)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    files.sort();
+    [DEFAULT_THEME, MONOCHROME_THEME]
+        .iter()
+        .map(|name| name.to_string())
+        .chain(files)
+        .collect()
+}
+
+fn themes_dir() -> std::path::PathBuf {
+    paths::config_dir().join(THEMES_DIR)
+}
+
+/// the color pair of a color, past pair 0 which the terminal keeps for itself
+fn pair(index: usize) -> i16 {
+    index as i16 + 1
+}
+
+fn parse_style(value: &str) -> Option<Style> {
+    let mut words = value.split_whitespace();
+    let basic = words.next().and_then(|name| {
+        BASIC_COLORS
+            .iter()
+            .find(|(basic, _)| *basic == name)
+            .map(|&(_, color)| color)
+    })?;
+    let mut style = Style {
+        basic,
+        extended: None,
+        attributes: A_NORMAL,
+    };
+    for word in words {
+        match word {
+            "bold" => style.attributes |= A_BOLD,
+            "dim" => style.attributes |= A_DIM,
+            "underline" => style.attributes |= A_UNDERLINE,
+            "reverse" => style.attributes |= A_REVERSE,
+            _ => style.extended = Some(word.parse::<u8>().ok()? as i16),
+        }
+    }
+    Some(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_are_a_color_then_a_shade_and_attributes() {
+        assert_eq!(
+            parse_style("green 70 bold"),
+            Some(Style::new(pancurses::COLOR_GREEN, 70, A_BOLD))
+        );
+        assert_eq!(
+            parse_style("red underline reverse"),
+            Some(Style {
+                basic: pancurses::COLOR_RED,
+                extended: None,
+                attributes: A_UNDERLINE | A_REVERSE,
+            })
+        );
+        assert_eq!(parse_style("purple"), None);
+        assert_eq!(parse_style("blue 256"), None);
+        assert_eq!(parse_style("blue shiny"), None);
+        assert_eq!(parse_style(""), None);
+    }
+
+    #[test]
+    fn theme_files_change_the_default_theme() {
+        let theme = Theme::parse(
+            "mine",
+            "# a comment\n\nhostile = green 70 bold\nwall = nope\nsky = blue\nfloor\n",
+        );
+        assert_eq!(theme.name, "mine");
+        assert_eq!(
+            theme.styles[Color::Hostile as usize],
+            Style::new(pancurses::COLOR_GREEN, 70, A_BOLD)
+        );
+        // lines that can't be read leave the default alone
+        assert_eq!(
+            theme.styles[Color::Wall as usize],
+            default_style(Color::Wall)
+        );
+        assert_eq!(
+            theme.styles[Color::Floor as usize],
+            default_style(Color::Floor)
+        );
+    }
+
+    #[test]
+    fn monochrome_tells_colors_apart_by_attributes() {
+        let theme = Theme::load(MONOCHROME_THEME);
+        assert_eq!(theme.attributes(Color::Hostile), A_BOLD);
+        assert_eq!(theme.attributes(Color::Remembered), A_DIM);
+        assert_eq!(theme.attributes(Color::Gold), A_NORMAL);
+    }
+}
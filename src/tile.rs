@@ -1,18 +1,35 @@
 use crate::map_gen;
 use crate::map_gen::{Rect, MAX_ROOMS, ROOM_MAX_SIZE, ROOM_MIN_SIZE};
-use crate::object::Object;
+use crate::object::Renderable;
 use crate::object_gen;
+use crate::rng;
+use crate::shop;
+use crate::theme::Color;
+use crate::world::World;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-pub const MAP_WIDTH: i32 = 100;
-pub const MAP_HEIGHT: i32 = 30;
+// the first level is this big, and every level down grows by a step until the largest size
+const FIRST_MAP_WIDTH: i32 = 100;
+const FIRST_MAP_HEIGHT: i32 = 30;
+const MAP_WIDTH_STEP: i32 = 12;
+const MAP_HEIGHT_STEP: i32 = 4;
+const MAX_MAP_WIDTH: i32 = 180;
+const MAX_MAP_HEIGHT: i32 = 60;
 
-#[derive(Clone, Copy, Debug)]
+const SHOP_MIN_LEVEL: u32 = 2;
+const SHOP_CHANCE: u32 = 3; // one in three levels
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Tile {
     pub blocked: bool,
     pub block_sight: bool,
+    /// seen at some point, so it is remembered
     pub visible: bool,
+    /// in view right now
     pub currently_visible: bool,
+    /// what was lying here when it was last in view, drawn while it is out of view
+    pub remembered: Option<Renderable>,
 }
 
 impl Tile {
@@ -22,6 +39,7 @@ impl Tile {
             block_sight: false,
             visible: false,
             currently_visible: false,
+            remembered: None,
         }
     }
 
@@ -31,27 +49,54 @@ impl Tile {
             block_sight: true,
             visible: false,
             currently_visible: false,
+            remembered: None,
         }
     }
 }
 
 pub type Map = Vec<Vec<Tile>>;
 
-pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
-    let mut map = vec![vec![Tile::wall(); MAP_HEIGHT as usize]; MAP_WIDTH as usize];
+/// the width and height of the map on a dungeon level
+pub fn level_size(level: u32) -> (i32, i32) {
+    let steps = level.saturating_sub(1) as i32;
+    (
+        (FIRST_MAP_WIDTH + steps * MAP_WIDTH_STEP).min(MAX_MAP_WIDTH),
+        (FIRST_MAP_HEIGHT + steps * MAP_HEIGHT_STEP).min(MAX_MAP_HEIGHT),
+    )
+}
+
+pub fn width(map: &Map) -> i32 {
+    map.len() as i32
+}
+
+pub fn height(map: &Map) -> i32 {
+    map.first().map_or(0, |column| column.len() as i32)
+}
+
+pub fn in_bounds(x: i32, y: i32, map: &Map) -> bool {
+    x >= 0 && y >= 0 && x < width(map) && y < height(map)
+}
+
+pub fn make_map(world: &mut World, level: u32) -> Map {
+    let (map_width, map_height) = level_size(level);
+    let mut map = vec![vec![Tile::wall(); map_height as usize]; map_width as usize];
+    // bigger levels get more rooms, as many for their size as the first level
+    let max_rooms = MAX_ROOMS * map_width * map_height / (FIRST_MAP_WIDTH * FIRST_MAP_HEIGHT);
 
     let mut rooms = vec![];
+    // only the rooms that were actually carved out
+    let mut created_rooms = vec![];
 
-    for _ in 0..MAX_ROOMS {
+    for _ in 0..max_rooms {
         // random width and height
-        let w = rand::thread_rng().gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
-        let h = rand::thread_rng().gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
+        let w = rng::get().gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
+        let h = rng::get().gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
         // random position without going out of the boundaries of the map
-        let x = rand::thread_rng().gen_range(1, MAP_WIDTH - w);
-        let y = rand::thread_rng().gen_range(1, MAP_HEIGHT - h);
+        let x = rng::get().gen_range(1, map_width - w);
+        let y = rng::get().gen_range(1, map_height - h);
 
         let new_room = Rect::new(x, y, w, h);
-        object_gen::spawn(new_room, objects, &map, level);
+        object_gen::spawn(new_room, world, &map, level);
 
         let failed = rooms
             .iter()
@@ -59,19 +104,20 @@ pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
 
         if !failed {
             map_gen::create_room(new_room, &mut map);
+            created_rooms.push(new_room);
 
             // center coordinates of the new room, will be useful later
             let (new_x, new_y) = new_room.center();
 
             if rooms.is_empty() {
                 // this is the first room, where the player starts at
-                objects[0].x = new_x;
-                objects[0].y = new_y;
+                let player_id = world.player_id();
+                world.set_pos(player_id, new_x, new_y);
             } else {
                 let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
 
                 // toss a coin (random bool value -- either true or false)
-                if rand::random() {
+                if rng::get().gen() {
                     // first move horizontally, then vertically
                     map_gen::create_h_tunnel(prev_x, new_x, prev_y, &mut map);
                     map_gen::create_v_tunnel(prev_y, new_y, new_x, &mut map);
@@ -86,42 +132,48 @@ pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
         rooms.push(new_room);
     }
 
-    let (mut last_room_x, mut last_room_y) = rooms[rooms.len() - 1].center();
-
-    while is_blocked(last_room_x, last_room_y, &map, objects) {
-        last_room_y += rand::thread_rng().gen_range(-1, 1);
-        last_room_x += rand::thread_rng().gen_range(-1, 1);
-
-        if last_room_x > MAP_WIDTH || last_room_x < 0 || last_room_y < 0 || last_room_y > MAP_HEIGHT
-        {
-            let (x, y) = rooms[rooms.len() - rand::thread_rng().gen_range(1, 4)].center();
+    // the stairs go in the last room that was carved out, on a free tile of it
+    let last_room = created_rooms[created_rooms.len() - 1];
+    let (mut last_room_x, mut last_room_y) = last_room.center();
+    if is_blocked(last_room_x, last_room_y, &map, world) {
+        let free = (last_room.x1..last_room.x2)
+            .flat_map(|x| (last_room.y1..last_room.y2).map(move |y| (x, y)))
+            .find(|&(x, y)| !is_blocked(x, y, &map, world));
+        if let Some((x, y)) = free {
             last_room_x = x;
             last_room_y = y;
         }
     }
 
-    let stairs = Object::new(
+    let stairs = world.spawn_at(
         last_room_x,
         last_room_y,
         '>',
-        pancurses::COLOR_RED,
+        Color::Stairs,
         true,
         "stairs",
         false,
     );
+    world.stairs.insert(stairs, ());
 
-    objects.push(stairs);
+    if level >= SHOP_MIN_LEVEL && rng::get().gen_range(0, SHOP_CHANCE) == 0 {
+        // never turn the starting room or the one with the stairs into a shop
+        let candidates = created_rooms[1..]
+            .iter()
+            .filter(|room| !room.contains(last_room_x, last_room_y))
+            .cloned()
+            .collect::<Vec<_>>();
+        shop::place_shop(&candidates, &map, world);
+    }
 
     map
 }
 
-pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
+pub fn is_blocked(x: i32, y: i32, map: &Map, world: &World) -> bool {
     // first test the map tile
     if map[x as usize][y as usize].blocked {
         return true;
     }
     // now check for any blocking objects
-    objects
-        .iter()
-        .any(|object| object.blocks && object.pos() == (x, y))
+    world.at(x, y).any(|id| world.blockers.contains(id))
 }
@@ -0,0 +1,193 @@
+use crate::ai::AI;
+use crate::item::{Equipment, Item};
+use crate::object::{DamageSource, Experience, Fighter, Position, Renderable, Vitals};
+use crate::store::{Entities, ObjectId, Storage};
+use crate::theme::Color;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything on the level and everything carried. An object is just an id;
+/// what it is comes from the components it has, so e.g. only objects with a
+/// position are on the map and only objects with a fighter can be attacked.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct World {
+    entities: Entities,
+    player: Option<ObjectId>,
+    pub names: Storage<String>,
+    pub positions: Storage<Position>,
+    pub renderables: Storage<Renderable>,
+    /// objects nothing else can walk through
+    pub blockers: Storage<()>,
+    /// the way down to the next level
+    pub stairs: Storage<()>,
+    pub fighters: Storage<Fighter>,
+    pub ais: Storage<AI>,
+    pub items: Storage<Item>,
+    pub equipment: Storage<Equipment>,
+    /// what an object carries; carried objects have no position of their own
+    pub inventories: Storage<Vec<ObjectId>>,
+    pub experience: Storage<Experience>,
+    pub vitals: Storage<Vitals>,
+    /// a pile on the floor, or the purse of whoever has a fighter too
+    pub gold: Storage<i32>,
+    /// items on a shop's shelves, with the shopkeeper who sells them
+    pub for_sale: Storage<ObjectId>,
+    pub charges: Storage<i32>,
+    pub killed_by: Storage<DamageSource>,
+}
+
+impl World {
+    /// a new object without any components
+    pub fn spawn(&mut self) -> ObjectId {
+        self.entities.create()
+    }
+
+    /// a new object drawn on the map, the components most objects start with
+    #[allow(clippy::too_many_arguments)]
+    pub fn spawn_at(
+        &mut self,
+        x: i32,
+        y: i32,
+        ch: char,
+        color: Color,
+        is_bold: bool,
+        name: &str,
+        blocks: bool,
+    ) -> ObjectId {
+        let id = self.spawn();
+        self.names.insert(id, name.to_string());
+        self.positions.insert(id, Position { x, y });
+        self.renderables
+            .insert(id, Renderable { ch, color, is_bold });
+        if blocks {
+            self.blockers.insert(id, ());
+        }
+        id
+    }
+
+    /// remove an object with all of its components, and everything it carries
+    pub fn despawn(&mut self, id: ObjectId) {
+        if !self.entities.destroy(id) {
+            return;
+        }
+        if self.player == Some(id) {
+            self.player = None;
+        }
+        for carried in self.inventories.retire(id).unwrap_or_default() {
+            self.despawn(carried);
+        }
+        // whoever carried it doesn't anymore
+        for (_, inventory) in self.inventories.iter_mut() {
+            inventory.retain(|&item| item != id);
+        }
+        self.names.retire(id);
+        self.positions.retire(id);
+        self.renderables.retire(id);
+        self.blockers.retire(id);
+        self.stairs.retire(id);
+        self.fighters.retire(id);
+        self.ais.retire(id);
+        self.items.retire(id);
+        self.equipment.retire(id);
+        self.experience.retire(id);
+        self.vitals.retire(id);
+        self.gold.retire(id);
+        self.for_sale.retire(id);
+        self.charges.retire(id);
+        self.killed_by.retire(id);
+    }
+
+    /// everything on the level goes, only the player and what they carry stay
+    pub fn clear_level(&mut self) {
+        for id in self.positions.ids() {
+            if Some(id) != self.player {
+                self.despawn(id);
+            }
+        }
+        // nobody is left to pay for what was taken from a shop
+        for id in self.for_sale.ids() {
+            self.for_sale.remove(id);
+        }
+    }
+
+    /// a shopkeeper is gone, their stock is anyone's
+    pub fn release_stock(&mut self, shopkeeper: ObjectId) {
+        for id in self.for_sale.ids() {
+            if self.for_sale[id] == shopkeeper {
+                self.for_sale.remove(id);
+            }
+        }
+    }
+
+    pub fn set_player(&mut self, id: ObjectId) {
+        self.player = Some(id);
+    }
+
+    pub fn player_id(&self) -> ObjectId {
+        self.player.expect("there is always a player")
+    }
+
+    /// the name of an object, empty for the rare object without one
+    pub fn name(&self, id: ObjectId) -> &str {
+        self.names.get(id).map_or("", |name| name.as_str())
+    }
+
+    /// where an object is on the map, for objects known to be on it
+    pub fn pos(&self, id: ObjectId) -> (i32, i32) {
+        let position = self.positions[id];
+        (position.x, position.y)
+    }
+
+    pub fn set_pos(&mut self, id: ObjectId, x: i32, y: i32) {
+        self.positions.insert(id, Position { x, y });
+    }
+
+    /// the distance between two objects on the map
+    pub fn distance(&self, a: ObjectId, b: ObjectId) -> f32 {
+        let (ax, ay) = self.pos(a);
+        let (bx, by) = self.pos(b);
+        (((bx - ax).pow(2) + (by - ay).pow(2)) as f32).sqrt()
+    }
+
+    /// objects that can still fight; the dead keep their fighter as a corpse
+    pub fn is_alive(&self, id: ObjectId) -> bool {
+        self.fighters.get(id).is_some_and(|fighter| fighter.hp > 0)
+    }
+
+    /// the objects on a tile
+    pub fn at(&self, x: i32, y: i32) -> impl Iterator<Item = ObjectId> + '_ {
+        self.positions
+            .iter()
+            .filter(move |(_, position)| (position.x, position.y) == (x, y))
+            .map(|(id, _)| id)
+    }
+
+    pub fn inventory(&self, id: ObjectId) -> &[ObjectId] {
+        self.inventories
+            .get(id)
+            .map_or(&[], |items| items.as_slice())
+    }
+
+    /// move an item from the map into an object's inventory
+    pub fn give(&mut self, owner: ObjectId, item: ObjectId) {
+        self.positions.remove(item);
+        match self.inventories.get_mut(owner) {
+            Some(inventory) => inventory.push(item),
+            None => {
+                self.inventories.insert(owner, vec![item]);
+            }
+        }
+    }
+
+    /// take the item at a position of an object's inventory and put it on the
+    /// floor where the object stands, unequipped
+    pub fn drop_item(&mut self, owner: ObjectId, index: usize) -> ObjectId {
+        let item = self.inventories[owner].remove(index);
+        if let Some(equipment) = self.equipment.get_mut(item) {
+            equipment.equipped = false;
+        }
+        let (x, y) = self.pos(owner);
+        self.set_pos(item, x, y);
+        item
+    }
+}